@@ -0,0 +1,191 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        hash::hash,
+        instruction::Instruction,
+        program::{get_return_data, invoke},
+    },
+};
+use anchor_spl::token_interface::Mint;
+use token_metadata::{
+    cpi::accounts::{RelayUpdateField, UpdateField},
+    program::TokenMetadata,
+    UpdateFieldData,
+};
+
+declare_id!("JBmvhrBsR7aPu8HMYL1USAkRyWvhdUFHTbTiCz9dXqzz");
+
+#[program]
+pub mod authority_caller {
+    use super::*;
+
+    // Forward an update to the token metadata program through CPI
+    pub fn update_field(ctx: Context<ForwardUpdateField>, data: UpdateFieldData) -> Result<()> {
+        token_metadata::cpi::update_field(
+            CpiContext::new(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                UpdateField {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    update_authority: ctx.accounts.update_authority.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    instructions: Some(ctx.accounts.instructions.to_account_info()),
+                    change_log: None,
+                    fee_destination: None,
+                    #[cfg(feature = "event-cpi")]
+                    event_authority: event_authority(&ctx.accounts.event_authority)?,
+                    #[cfg(feature = "event-cpi")]
+                    program: ctx.accounts.token_metadata_program.to_account_info(),
+                },
+            ),
+            data,
+        )
+    }
+
+    // Forward an update signed by this program's delegate PDA
+    pub fn update_field_as_delegate(
+        ctx: Context<ForwardDelegatedUpdateField>,
+        data: UpdateFieldData,
+    ) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] = &[&[b"delegate", &[ctx.bumps.delegate]]];
+        token_metadata::cpi::update_field(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                UpdateField {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    update_authority: ctx.accounts.delegate.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    instructions: None,
+                    change_log: None,
+                    fee_destination: None,
+                    #[cfg(feature = "event-cpi")]
+                    event_authority: event_authority(&ctx.accounts.event_authority)?,
+                    #[cfg(feature = "event-cpi")]
+                    program: ctx.accounts.token_metadata_program.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            data,
+        )
+    }
+
+    // Forward a relayed update, the signature verification precedes this instruction
+    pub fn relay_update_field(
+        ctx: Context<ForwardRelayUpdateField>,
+        data: UpdateFieldData,
+        nonce: u64,
+    ) -> Result<()> {
+        token_metadata::cpi::relay_update_field(
+            CpiContext::new(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                RelayUpdateField {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    instructions: ctx.accounts.instructions.to_account_info(),
+                    fee_destination: None,
+                },
+            ),
+            data,
+            nonce,
+        )
+    }
+
+    // Read the CPI caller seen by the token metadata program, only works against its test builds
+    pub fn forward_get_cpi_caller(ctx: Context<ForwardGetCpiCaller>) -> Result<Option<Pubkey>> {
+        // Not in the program's IDL or CPI client, built from its Anchor discriminator
+        let instruction = Instruction {
+            program_id: ctx.accounts.token_metadata_program.key(),
+            accounts: vec![AccountMeta::new_readonly(
+                ctx.accounts.instructions.key(),
+                false,
+            )],
+            data: hash(b"global:get_cpi_caller").to_bytes()[..8].to_vec(),
+        };
+        invoke(
+            &instruction,
+            &[
+                ctx.accounts.instructions.to_account_info(),
+                ctx.accounts.token_metadata_program.to_account_info(),
+            ],
+        )?;
+        let (_, data) = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+        Ok(Option::<Pubkey>::try_from_slice(&data)?)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ForwardUpdateField<'info> {
+    /// CHECK: checked by the token metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, checked by the token metadata program
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub token_metadata_program: Program<'info, TokenMetadata>,
+    /// CHECK: Event authority of the token metadata program, required when it is built with
+    /// `event-cpi`
+    #[account(seeds = [b"__event_authority"], bump, seeds::program = token_metadata_program)]
+    pub event_authority: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ForwardDelegatedUpdateField<'info> {
+    /// CHECK: checked by the token metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: PDA of this program, signs as the delegate
+    #[account(seeds = [b"delegate"], bump)]
+    pub delegate: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_metadata_program: Program<'info, TokenMetadata>,
+    /// CHECK: Event authority of the token metadata program, required when it is built with
+    /// `event-cpi`
+    #[account(seeds = [b"__event_authority"], bump, seeds::program = token_metadata_program)]
+    pub event_authority: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ForwardRelayUpdateField<'info> {
+    /// CHECK: checked by the token metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, checked by the token metadata program
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub token_metadata_program: Program<'info, TokenMetadata>,
+}
+
+#[derive(Accounts)]
+pub struct ForwardGetCpiCaller<'info> {
+    /// CHECK: Instructions sysvar, checked by the token metadata program
+    pub instructions: UncheckedAccount<'info>,
+    pub token_metadata_program: Program<'info, TokenMetadata>,
+}
+
+// The event authority the token metadata program checks its self-CPI against
+#[cfg(feature = "event-cpi")]
+fn event_authority<'info>(
+    event_authority: &Option<UncheckedAccount<'info>>,
+) -> Result<AccountInfo<'info>> {
+    event_authority
+        .as_ref()
+        .map(|account| account.to_account_info())
+        .ok_or_else(|| ErrorCode::AccountNotEnoughKeys.into())
+}
@@ -0,0 +1,196 @@
+use anchor_lang::prelude::*;
+
+/// Errors of this program, numbered up from Anchor's custom error offset of 6000. Codes
+/// below it belong to Anchor, and the SPL interface errors returned as `ProgramError::Custom`
+/// start at 0x35c2b5bd, hashed from the interface name, so the ranges don't overlap.
+/// Append new variants to keep codes stable
+#[error_code]
+pub enum MetadataError {
+    /// Update authority account was provided with the default pubkey
+    #[msg("Update authority cannot be the default pubkey")]
+    InvalidUpdateAuthority,
+    /// Label is empty or longer than the maximum seed length
+    #[msg("Label must be between 1 and 32 bytes")]
+    InvalidLabel,
+    /// Mint authority has been set to None on the mint
+    #[msg("Mint authority has been revoked")]
+    MintAuthorityRevoked,
+    /// Computing the serialized size of the metadata failed
+    #[msg("Failed to serialize token metadata")]
+    MetadataSerializationFailed,
+    /// Approvals are required and the mint has not been approved by the admin
+    #[msg("Mint is not approved")]
+    MintNotApproved,
+    /// Custom key uses the reserved prefix
+    #[msg("Key uses the reserved prefix")]
+    ReservedKey,
+    /// Symbol policy requires uppercase ASCII
+    #[msg("Symbol must be uppercase")]
+    SymbolNotUppercase,
+    /// No oracle was set for the metadata
+    #[msg("Oracle is not set")]
+    OracleNotSet,
+    /// Signer is not the oracle stored in the metadata
+    #[msg("Incorrect oracle")]
+    IncorrectOracle,
+    /// Packed metadata does not match the computed account size
+    #[msg("Metadata size mismatch")]
+    SizeMismatch,
+    /// More accounts were passed than a batched instruction accepts
+    #[msg("Too many accounts")]
+    TooManyAccounts,
+    /// Display decimals exceed the decimals of the mint
+    #[msg("Display decimals exceed mint decimals")]
+    InvalidDisplayDecimals,
+    /// Confirmation does not match the mint
+    #[msg("Invalid confirmation")]
+    InvalidConfirmation,
+    /// Metadata account has not been initialized
+    #[msg("Metadata not initialized")]
+    MetadataNotInitialized,
+    /// Field is locked and can't be changed
+    #[msg("Field is locked")]
+    FieldLocked,
+    /// Requested byte range is outside the account data or too long to return
+    #[msg("Range out of bounds")]
+    RangeOutOfBounds,
+    /// Threshold is zero or exceeds the number of members
+    #[msg("Invalid threshold")]
+    InvalidThreshold,
+    /// Signer is not a member of the governance
+    #[msg("Not a governance member")]
+    NotGovernanceMember,
+    /// Member already approved the proposal
+    #[msg("Already voted")]
+    AlreadyVoted,
+    /// Proposal does not have enough approvals
+    #[msg("Threshold not met")]
+    ThresholdNotMet,
+    /// Name, symbol and uri are all empty
+    #[msg("Metadata is empty")]
+    EmptyMetadata,
+    /// Value is longer than `MAX_VALUE_LEN`
+    #[msg("Value too long")]
+    ValueTooLong,
+    /// Key already exists in the metadata
+    #[msg("Duplicate key")]
+    DuplicateKey,
+    /// Metadata changed since the snapshot was taken
+    #[msg("Snapshot mismatch")]
+    SnapshotMismatch,
+    /// Symbol is longer than `MAX_SYMBOL_LEN`
+    #[msg("Symbol too long")]
+    SymbolTooLong,
+    /// Metadata account was passed as the payer
+    #[msg("Invalid payer")]
+    InvalidPayer,
+    /// Royalty exceeds 100%, or creator shares don't add up to 100
+    #[msg("Invalid royalties")]
+    InvalidRoyalties,
+    /// No claimant is pending for the update authority
+    #[msg("No pending claim")]
+    NoPendingClaim,
+    /// Signer is not the pending claimant
+    #[msg("Incorrect claimant")]
+    IncorrectClaimant,
+    /// Name is longer than `MAX_NAME_LEN`
+    #[msg("Name too long")]
+    NameTooLong,
+    /// Update was not invoked through CPI from the authority program
+    #[msg("Not invoked by the authority program")]
+    NotInvokedByAuthorityProgram,
+    /// More keys matched than a single instruction rewrites
+    #[msg("Too many keys")]
+    TooManyKeys,
+    /// Maximum supply is below the current supply of the mint
+    #[msg("Max supply below current supply")]
+    MaxSupplyBelowSupply,
+    /// MIME type is not of the form `type/subtype`
+    #[msg("Invalid MIME type")]
+    InvalidMimeType,
+    /// No commitment was made for the revealed field
+    #[msg("No commitment for field")]
+    NoCommitment,
+    /// Revealed value and salt don't hash to the commitment
+    #[msg("Reveal does not match commitment")]
+    CommitmentMismatch,
+    /// Locale is not a short BCP 47 style tag
+    #[msg("Invalid locale")]
+    InvalidLocale,
+    /// Metadata account would exceed `MAX_METADATA_SIZE`
+    #[msg("Metadata too large")]
+    MetadataTooLarge,
+    /// Mint supply is below the threshold required for updates
+    #[msg("Supply threshold not met")]
+    SupplyThresholdNotMet,
+    /// Symbol is already claimed by another mint
+    #[msg("Symbol already taken")]
+    SymbolAlreadyTaken,
+    /// URI is not of the form `scheme://rest`
+    #[msg("Invalid URI")]
+    InvalidUri,
+    /// Relayed update is not signed by the update authority through the Ed25519 program
+    #[msg("Invalid signed update")]
+    InvalidSignedUpdate,
+    /// Relayed update nonce is not larger than the last one used
+    #[msg("Nonce already used")]
+    NonceAlreadyUsed,
+    /// Metadata can't be changed after its lock time
+    #[msg("Metadata locked by time")]
+    MetadataLockedByTime,
+    /// Mint has a freeze authority, rejected by the policy
+    #[msg("Mint has a freeze authority")]
+    MintFrozen,
+    /// Name is shorter than the policy minimum
+    #[msg("Name too short")]
+    NameTooShort,
+    /// Symbol is shorter than the policy minimum
+    #[msg("Symbol too short")]
+    SymbolTooShort,
+    /// Attributes aren't a well-formed JSON value
+    #[msg("Invalid JSON")]
+    InvalidJson,
+    /// Custom key is not on the policy allowlist
+    #[msg("Key not allowed")]
+    KeyNotAllowed,
+    /// Custom key is named like a core field, which must be set through its own variant
+    #[msg("Key collides with a core field")]
+    KeyCollidesWithCoreField,
+    /// Fee destination is missing or differs from the one set in the policy
+    #[msg("Incorrect fee destination")]
+    IncorrectFeeDestination,
+    /// Attestation is not signed by the attester set in the policy
+    #[msg("Untrusted attester")]
+    UntrustedAttester,
+    /// Update authority can only be changed by the admin set in the policy
+    #[msg("Signer is not the admin")]
+    NotAdmin,
+    /// Update authority has no lamports to pay its transaction fees
+    #[msg("Update authority account is unfunded")]
+    AuthorityUnfunded,
+    /// Hidden fields were already revealed, which can't be undone
+    #[msg("Metadata already revealed")]
+    AlreadyRevealed,
+    /// Custom key doesn't start with the prefix required by the policy
+    #[msg("Key must start with the required prefix")]
+    KeyPrefixRequired,
+    /// Packed metadata would grow past the maximum total size set by the policy
+    #[msg("Metadata exceeds the maximum total size")]
+    MaxSizeExceeded,
+    /// Payer doesn't have the lamports to keep the grown metadata account rent exempt
+    #[msg("Payer has insufficient funds for the rent")]
+    PayerInsufficientFunds,
+    /// No longer returned, builds without the `test-helpers` feature leave the test-only
+    /// instructions out entirely. Kept so the codes after it don't shift
+    #[msg("Test helpers are disabled in this build")]
+    TestHelpersDisabled,
+    /// Symbol is claimed by the mint, so it can't change until the metadata is closed
+    #[msg("Claimed symbol can't change")]
+    SymbolClaimed,
+    /// Write-once key already has a value
+    #[msg("Key already exists")]
+    KeyAlreadyExists,
+    /// Governance members contain the same key more than once
+    #[msg("Duplicate governance member")]
+    DuplicateMember,
+}
@@ -2,8 +2,18 @@ use anchor_lang::{
     prelude::*,
     system_program::{create_account, transfer, CreateAccount, Transfer},
 };
-use anchor_spl::token_interface::Mint;
-use solana_program::borsh0_10::get_instance_packed_len;
+use anchor_spl::token_interface::{Mint, TokenInterface};
+use solana_program::{
+    borsh0_10::get_instance_packed_len,
+    ed25519_program,
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    hash::{hash, hashv, Hash},
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+    program::{set_return_data, MAX_RETURN_DATA},
+    pubkey::MAX_SEED_LEN,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use spl_discriminator::SplDiscriminate;
 use spl_pod::optional_keys::OptionalNonZeroPubkey;
 use spl_token_metadata_interface::{
     error::TokenMetadataError,
@@ -14,8 +24,131 @@ use spl_type_length_value::state::{
     realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed, TlvStateMut,
 };
 
+pub mod error;
+pub mod metaplex;
+pub mod policy;
+pub mod state;
+#[cfg(feature = "test-helpers")]
+mod test_helpers;
+use error::MetadataError;
+use metaplex::{to_metaplex, write_royalties, Creator, MetaplexData};
+use policy::{
+    check_authority_funded, check_field_unlocked, check_freeze_authority, check_max_total_size,
+    check_supply_threshold, check_time_lock, enforce_policy, record_updated_slot, write_policy,
+    Policy, ADMIN_KEY, ATTESTER_KEY, AUTHORITY_PROGRAM_KEY, CLAIMANT_KEY, FEE_DESTINATION_KEY,
+    ORACLE_KEY, ORACLE_KEY_PREFIX, REJECT_FREEZABLE_KEY, REQUIRE_FUNDED_AUTHORITY_KEY,
+    SUPPLY_THRESHOLD_KEY, UPDATED_SLOT_KEY, UPDATE_FEE_KEY,
+};
+use state::{
+    ChangeLog, ChangeRecord, Config, Governance, MintApproval, Proposal, Registry, RegistryPage,
+    SymbolClaim, VersionedProposal, MAX_GOVERNANCE_MEMBERS, PROPOSAL_VERSION, REGISTRY_PAGE_SIZE,
+};
+use std::str::FromStr;
+
 declare_id!("9G9qb4bwYTywRLwXevYBMZ2AErdxAYUTnkaNf2t3RsgE");
 
+/// Reserved keys holding display hints for wallets
+pub const DISPLAY_DECIMALS_KEY: &str = "__display.decimals";
+pub const DISPLAY_PREFIX_KEY: &str = "__display.prefix";
+pub const DISPLAY_SUFFIX_KEY: &str = "__display.suffix";
+
+/// Prefix of keys storing the placeholder returned by reads in place of a field until `reveal`
+pub const HIDDEN_KEY_PREFIX: &str = "__hidden.";
+
+/// Reserved key present once `reveal` was called, reads then return the hidden fields
+pub const REVEALED_KEY: &str = "__revealed";
+
+/// Prefix of keys storing the commitment to a field's revealed value
+pub const COMMIT_KEY_PREFIX: &str = "__commit.";
+
+/// Prefix of keys storing localized variants, as `__locale.<locale>.name` and `.symbol`
+pub const LOCALE_KEY_PREFIX: &str = "__locale.";
+
+/// Maximum byte length of a locale tag
+pub const MAX_LOCALE_LEN: usize = 16;
+
+/// Reserved key storing the last nonce of a relayed update, each relayed update needs a larger one
+pub const RELAY_NONCE_KEY: &str = "__relay_nonce";
+
+/// Reserved key storing the MIME type of the content the URI points to
+pub const URI_CONTENT_TYPE_KEY: &str = "__uri.content_type";
+
+/// Reserved key storing the URI clients can try when the primary URI is unreachable
+pub const URI_FALLBACK_KEY: &str = "__uri.fallback";
+
+/// Reserved key storing structured attributes, validated as JSON by `set_json_attributes`
+pub const ATTRIBUTES_KEY: &str = "__attributes";
+
+/// Maximum nesting of arrays and objects in JSON attributes, bounds the validator's recursion
+pub const MAX_JSON_DEPTH: usize = 16;
+
+/// Value replacing a deprecated custom key, keeps a record that the key existed
+pub const TOMBSTONE: &str = "__deprecated";
+
+/// Reserved keys storing the attester of the metadata and the attestation hash it signed
+pub const ATTESTED_BY_KEY: &str = "__attested_by";
+pub const ATTESTED_HASH_KEY: &str = "__attested_hash";
+
+/// Reserved key storing the maximum supply hint
+pub const MAX_SUPPLY_KEY: &str = "__max_supply";
+
+/// Reserved key storing the `MetadataFlag` bitmask as a decimal integer, absent when no flag is set
+pub const FLAGS_KEY: &str = "__flags";
+
+/// Reserved key storing the delegate allowed to update fields
+pub const DELEGATE_KEY: &str = "__delegate";
+
+/// Reserved key storing the program whose PDA is the delegate, set by `set_program_delegate`
+pub const DELEGATE_PROGRAM_KEY: &str = "__delegate.program";
+
+/// Reserved key storing the signer of the last `update_field`, the update authority or its delegate
+pub const LAST_UPDATER_KEY: &str = "__last_updater";
+
+/// Reserved key present when refunds from a delegate's shrink are held for the authority
+pub const DELEGATE_HOLD_REFUNDS_KEY: &str = "__delegate.hold_refunds";
+
+/// Maximum byte length of a single value written by `update_field`, bounds the cost of each field
+/// well below both the u32 borsh length prefix and the transaction size limit
+pub const MAX_VALUE_LEN: usize = 512;
+
+/// Maximum size of a metadata account, the most a single CPI can create,
+/// so the rent and transfer math never sees an unbounded size
+pub const MAX_METADATA_SIZE: usize = MAX_PERMITTED_DATA_INCREASE;
+
+/// Maximum number of keys rewritten by a single `remap_keys`, keeps it within the compute budget
+pub const MAX_REMAP_KEYS: usize = 32;
+
+/// Maximum number of custom keys returned by a single `get_metadata_page`,
+/// keeps a page of short values within the return data limit
+pub const MAX_PAGE_KEYS: usize = 10;
+
+/// Maximum number of keys read by a single `get_keys`, keeps it within the compute budget
+pub const MAX_GET_KEYS: usize = 16;
+
+/// Length prefix returned by `get_keys` for a missing key,
+/// never a valid length as values are bounded by `MAX_VALUE_LEN`
+pub const MISSING_KEY_SENTINEL: u16 = u16::MAX;
+
+/// Maximum number of metadata accounts read in a single batched instruction,
+/// keeps the returned list of mints well within the return data limit
+pub const MAX_BATCH_ACCOUNTS: usize = 16;
+
+/// Maximum number of metadata accounts created by a single batch initialize,
+/// three accounts per item keep the transaction within its size limit
+pub const MAX_BATCH_INITIALIZE: usize = 8;
+
+/// Prefix of custom keys managed by the program, which can't be set through update_field
+pub const RESERVED_KEY_PREFIX: &str = "__";
+
+/// Reserved key holding a hash of all other metadata fields
+pub const INTEGRITY_KEY: &str = "__integrity";
+
+/// Reserved key storing the symbol claimed at initialize, which can't change while claimed
+pub const SYMBOL_CLAIM_KEY: &str = "__symbol_claim";
+
+/// Seed of the metadata PDA before the migration to `[b"metadata", mint]`, followed by the mint
+pub const LEGACY_METADATA_SEED: &[u8] = b"token-metadata";
+
 #[program]
 pub mod token_metadata {
 
@@ -26,8 +159,14 @@ pub mod token_metadata {
         accounts: &'info [AccountInfo<'info>],
         data: &[u8],
     ) -> Result<()> {
+        #[cfg(feature = "test-helpers")]
+        if let Some(result) = test_helpers::dispatch(program_id, accounts, data) {
+            return result;
+        }
         let instruction = TokenMetadataInstruction::unpack(data)?;
 
+        // The __global handlers deserialize and validate the accounts exactly as for
+        // Anchor instructions, so seeds constraints also hold for the raw interface
         match instruction {
             TokenMetadataInstruction::Initialize(data) => {
                 msg!("Instruction: Anchor Initialize");
@@ -44,36 +183,430 @@ pub mod token_metadata {
                     .map_err(|_| ProgramError::InvalidInstructionData)?;
                 __private::__global::update_field(program_id, accounts, &serialized_data)
             }
-            _ => return Err(ProgramError::InvalidInstructionData.into()),
+            _ => Err(ProgramError::InvalidInstructionData.into()),
         }
     }
 
     pub fn initialize(ctx: Context<Initialize>, data: InitializeData) -> Result<()> {
-        // Check if update authority is provided
-        let update_authority_key = ctx
-            .accounts
-            .update_authority
-            .as_ref()
-            .map(|account| *account.key);
-        let update_authority = OptionalNonZeroPubkey::try_from(update_authority_key)
-            .map_err(|_| ProgramError::InvalidArgument)?;
+        initialize_with_policy(ctx, data, Policy::default())
+    }
+
+    pub fn initialize_with_policy(
+        ctx: Context<Initialize>,
+        data: InitializeData,
+        policy: Policy,
+    ) -> Result<()> {
+        process_initialize(ctx, |update_authority, mint| {
+            new_token_metadata(update_authority, mint, data, &policy)
+        })
+    }
+
+    pub fn initialize_immutable(ctx: Context<Initialize>, data: InitializeData) -> Result<()> {
+        // Created without an update authority in the same instruction, so there is no window
+        // in which it could be edited. Reject one passed in rather than silently dropping it
+        require!(
+            ctx.accounts.update_authority.is_none(),
+            MetadataError::InvalidUpdateAuthority
+        );
+        process_initialize(ctx, |_, mint| {
+            new_token_metadata(None, mint, data, &Policy::default())
+        })
+    }
+
+    pub fn initialize_with_keys(
+        ctx: Context<Initialize>,
+        data: InitializeData,
+        additional_metadata: Vec<MetadataEntry>,
+    ) -> Result<()> {
+        check_initial_keys(
+            additional_metadata
+                .iter()
+                .map(|entry| (entry.key.as_str(), entry.value.as_str())),
+        )?;
+
+        process_initialize(ctx, |update_authority, mint| {
+            let mut token_metadata =
+                new_token_metadata(update_authority, mint, data, &Policy::default())?;
+            for MetadataEntry { key, value } in additional_metadata {
+                token_metadata.update(Field::Key(key), value);
+            }
+            // Again, so the integrity hash covers the keys
+            finalize_metadata(&mut token_metadata)?;
+            Ok(token_metadata)
+        })
+    }
+
+    pub fn initialize_raw(
+        ctx: Context<Initialize>,
+        data: InitializeData,
+        additional_metadata: Vec<u8>,
+    ) -> Result<()> {
+        // The whole payload is bounded like a single update_field value
+        require!(
+            additional_metadata.len() <= MAX_VALUE_LEN,
+            MetadataError::ValueTooLong
+        );
+        // Encoded like the stored additional_metadata, borsh still checks the strings are UTF-8
+        // as readers couldn't deserialize the metadata otherwise
+        let entries = Vec::<(String, String)>::try_from_slice(&additional_metadata)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        check_initial_keys(
+            entries
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )?;
+
+        process_initialize(ctx, |update_authority, mint| {
+            let mut token_metadata =
+                new_token_metadata(update_authority, mint, data, &Policy::default())?;
+            for (key, value) in entries {
+                token_metadata.update(Field::Key(key), value);
+            }
+            // Again, so the integrity hash covers the keys
+            finalize_metadata(&mut token_metadata)?;
+            Ok(token_metadata)
+        })
+    }
+
+    pub fn initialize_empty(ctx: Context<Initialize>) -> Result<()> {
+        // Reserves the PDA at its minimal size, update_field fills in the fields later
+        process_initialize(ctx, |update_authority, mint| {
+            build_token_metadata(
+                update_authority,
+                mint,
+                InitializeData {
+                    name: String::new(),
+                    symbol: String::new(),
+                    uri: String::new(),
+                },
+                &Policy::default(),
+            )
+        })
+    }
+
+    pub fn close_metadata(ctx: Context<CloseMetadata>) -> Result<()> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        check_update_authority(
+            &token_metadata,
+            ctx.accounts.update_authority.key,
+            ctx.accounts.instructions.as_deref(),
+        )?;
+        check_time_lock(&token_metadata)?;
+
+        // Free the symbol, if this mint claimed it
+        let symbol = find_key_value(&token_metadata, SYMBOL_CLAIM_KEY)
+            .unwrap_or(token_metadata.symbol.as_str());
+        let symbol_claim = &ctx.accounts.symbol_claim;
+        if symbol_claim.key != &symbol_claim_address(symbol).0 {
+            return Err(ProgramError::InvalidSeeds.into());
+        }
+        if symbol_claim.owner == &id() {
+            let claim = SymbolClaim::try_deserialize(&mut &symbol_claim.try_borrow_data()?[..])?;
+            if claim.mint == ctx.accounts.mint.key() {
+                msg!("Freeing symbol {}", symbol);
+                close_account(symbol_claim, &ctx.accounts.destination)?;
+            }
+        }
+
+        close_account(&ctx.accounts.metadata, &ctx.accounts.destination)
+    }
+
+    pub fn initialize_self(ctx: Context<InitializeSelf>, data: InitializeData) -> Result<()> {
+        // The authority acts as payer, mint authority and update authority
+        let mint = ctx.accounts.mint.key();
+        let bump = [ctx.bumps.metadata];
+        initialize_metadata(
+            InitializeAccounts {
+                metadata: &ctx.accounts.metadata,
+                metadata_seeds: &[b"metadata", mint.as_ref(), &bump],
+                update_authority: Some(&ctx.accounts.authority),
+                mint: &ctx.accounts.mint,
+                mint_key: mint,
+                mint_authority: ctx.accounts.authority.key,
+                payer: &ctx.accounts.authority,
+                system_program: &ctx.accounts.system_program,
+                config: &ctx.accounts.config,
+                approval: &ctx.accounts.approval,
+                registry: ctx.accounts.registry.as_deref_mut(),
+                registry_page: ctx.accounts.registry_page.as_deref(),
+                symbol_claim: ctx.accounts.symbol_claim.as_deref(),
+            },
+            |update_authority, mint| {
+                new_token_metadata(update_authority, mint, data, &Policy::default())
+            },
+        )?;
+        Ok(())
+    }
+
+    pub fn create_mint_and_metadata(
+        ctx: Context<CreateMintAndMetadata>,
+        _decimals: u8,
+        data: InitializeData,
+    ) -> Result<()> {
+        // The mint was already created and initialized through a CPI into the token program
+        // by the `init` constraint on the accounts, so the mint authority check always passes
+        let mint = ctx.accounts.mint.key();
+        let bump = [ctx.bumps.metadata];
+        initialize_metadata(
+            InitializeAccounts {
+                metadata: &ctx.accounts.metadata,
+                metadata_seeds: &[b"metadata", mint.as_ref(), &bump],
+                update_authority: ctx.accounts.update_authority.as_deref(),
+                mint: &ctx.accounts.mint,
+                mint_key: mint,
+                mint_authority: ctx.accounts.mint_authority.key,
+                payer: &ctx.accounts.payer,
+                system_program: &ctx.accounts.system_program,
+                config: &ctx.accounts.config,
+                approval: &ctx.accounts.approval,
+                registry: ctx.accounts.registry.as_deref_mut(),
+                registry_page: ctx.accounts.registry_page.as_deref(),
+                symbol_claim: ctx.accounts.symbol_claim.as_deref(),
+            },
+            |update_authority, mint| {
+                new_token_metadata(update_authority, mint, data, &Policy::default())
+            },
+        )?;
+        Ok(())
+    }
+
+    pub fn initialize_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeBatch<'info>>,
+        payload: Vec<u8>,
+    ) -> Result<()> {
+        // Slice the items out of the payload instead of deserializing a Vec<InitializeData>
+        let items = parse_compact_items(&payload)?;
+        process_initialize_batch(ctx.accounts, ctx.remaining_accounts, items)
+    }
+
+    pub fn initialize_batch_borsh<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeBatch<'info>>,
+        items: Vec<InitializeData>,
+    ) -> Result<()> {
+        // Baseline for `initialize_batch`, deserializing the items with borsh
+        process_initialize_batch(ctx.accounts, ctx.remaining_accounts, items)
+    }
 
-        // Construct token metadata
-        let token_metadata = TokenMetadata {
-            name: data.name,
-            symbol: data.symbol,
-            uri: data.uri,
-            update_authority,
+    pub fn update_field(ctx: Context<UpdateField>, data: UpdateFieldData) -> Result<()> {
+        #[cfg(feature = "event-cpi")]
+        let event = FieldUpdated {
             mint: ctx.accounts.mint.key(),
-            ..Default::default()
+            field: data.field.clone(),
+            value: data.value.clone(),
         };
+
+        process_update_field(
+            &ctx.accounts.metadata,
+            Some(&ctx.accounts.mint),
+            ctx.accounts.update_authority.key,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.instructions.as_deref(),
+            ctx.accounts.change_log.as_deref_mut(),
+            ctx.accounts.fee_destination.as_deref(),
+            data,
+        )?;
+
+        // Survives log truncation, unlike `emit!`
+        #[cfg(feature = "event-cpi")]
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn update_field_no_mint(
+        ctx: Context<UpdateFieldNoMint>,
+        data: UpdateFieldData,
+    ) -> Result<()> {
+        // The metadata stores its mint, which stands in for the mint account to check the PDA
+        let metadata = &ctx.accounts.metadata;
+        let mint = Pubkey::new_from_array(read_packed_key(metadata, 32)?);
+        let (expected, _) = Pubkey::find_program_address(&[b"metadata", mint.as_ref()], &id());
+        if metadata.key() != expected {
+            return err!(anchor_lang::error::ErrorCode::ConstraintSeeds);
+        }
+
+        process_update_field(
+            metadata,
+            None,
+            ctx.accounts.update_authority.key,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.instructions.as_deref(),
+            None,
+            ctx.accounts.fee_destination.as_deref(),
+            data,
+        )
+    }
+
+    pub fn relay_update_field(
+        ctx: Context<RelayUpdateField>,
+        data: UpdateFieldData,
+        nonce: u64,
+    ) -> Result<()> {
+        // The update authority signed the update off-chain, verified by the Ed25519 program
+        // in the previous instruction
+        let mint = ctx.accounts.mint.key();
+        let message = relayed_update_message(&mint, nonce, &data)?;
+        let signer = verify_ed25519_signer(&ctx.accounts.instructions, &message)?;
+
+        // Consume the nonce before the update, so even a no-op update can't be replayed
+        let mut token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        check_update_authority_or_delegate(
+            &token_metadata,
+            &signer,
+            Some(&ctx.accounts.instructions),
+        )?;
+        let last_nonce = find_key_value(&token_metadata, RELAY_NONCE_KEY)
+            .map(u64::from_str)
+            .transpose()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        require!(
+            !matches!(last_nonce, Some(last_nonce) if nonce <= last_nonce),
+            MetadataError::NonceAlreadyUsed
+        );
+        token_metadata.update(Field::Key(RELAY_NONCE_KEY.to_string()), nonce.to_string());
+        finalize_metadata(&mut token_metadata)?;
+        realloc_metadata(
+            &ctx.accounts.metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            &token_metadata,
+            false,
+        )?;
+
+        process_update_field(
+            &ctx.accounts.metadata,
+            Some(&ctx.accounts.mint),
+            &signer,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            Some(&ctx.accounts.instructions),
+            None,
+            ctx.accounts.fee_destination.as_deref(),
+            data,
+        )
+    }
+
+    pub fn verify_attestation(ctx: Context<VerifyAttestation>) -> Result<()> {
+        // The trusted attester signed the current content, verified by the Ed25519 program
+        // in the previous instruction
+        let mut token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        let hash = attestation_hash(&token_metadata)?;
+        let message = attestation_message(&ctx.accounts.mint.key(), &hash);
+        let signer = verify_ed25519_signer(&ctx.accounts.instructions, &message)?;
+        require!(
+            find_key_value(&token_metadata, ATTESTER_KEY) == Some(signer.to_string().as_str()),
+            MetadataError::UntrustedAttester
+        );
+
+        token_metadata.update(Field::Key(ATTESTED_BY_KEY.to_string()), signer.to_string());
+        token_metadata.update(Field::Key(ATTESTED_HASH_KEY.to_string()), hash.to_string());
+        finalize_metadata(&mut token_metadata)?;
         msg!("TokenMetadata: {:?}", token_metadata);
 
-        // Calculate size and lamports for the metadata account
-        let size = TokenMetadata::tlv_size_of(&token_metadata)?;
-        let lamports = Rent::get()?.minimum_balance(size as usize);
+        realloc_metadata(
+            &ctx.accounts.metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            &token_metadata,
+            false,
+        )
+    }
+
+    pub fn get_attestation(ctx: Context<ReadMetadata>) -> Result<Attestation> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        let hash = attestation_hash(&token_metadata)?;
+        let attester = find_key_value(&token_metadata, ATTESTED_BY_KEY)
+            .map(Pubkey::from_str)
+            .transpose()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Attestation {
+            hash: hash.to_bytes(),
+            // Any change after the attestation leaves it stale
+            attested: find_key_value(&token_metadata, ATTESTED_HASH_KEY)
+                == Some(hash.to_string().as_str()),
+            attester,
+        })
+    }
+
+    pub fn initialize_labeled(
+        ctx: Context<InitializeLabeled>,
+        label: String,
+        data: InitializeData,
+    ) -> Result<()> {
+        check_label(&label)?;
+
+        let mint = ctx.accounts.mint.key();
+        let bump = [ctx.bumps.metadata];
+        #[cfg_attr(not(feature = "event-cpi"), allow(unused_variables))]
+        let token_metadata = initialize_metadata(
+            InitializeAccounts {
+                metadata: &ctx.accounts.metadata,
+                metadata_seeds: &[b"metadata", mint.as_ref(), label.as_bytes(), &bump],
+                update_authority: ctx.accounts.update_authority.as_deref(),
+                mint: &ctx.accounts.mint,
+                mint_key: mint,
+                mint_authority: ctx.accounts.mint_authority.key,
+                payer: &ctx.accounts.payer,
+                system_program: &ctx.accounts.system_program,
+                config: &ctx.accounts.config,
+                approval: &ctx.accounts.approval,
+                registry: ctx.accounts.registry.as_deref_mut(),
+                registry_page: ctx.accounts.registry_page.as_deref(),
+                symbol_claim: ctx.accounts.symbol_claim.as_deref(),
+            },
+            |update_authority, mint| {
+                new_token_metadata(update_authority, mint, data, &Policy::default())
+            },
+        )?;
+
+        #[cfg(feature = "event-cpi")]
+        emit_cpi!(MetadataInitialized {
+            mint,
+            update_authority: Option::<Pubkey>::from(token_metadata.update_authority),
+        });
+        Ok(())
+    }
+
+    pub fn update_field_labeled(
+        ctx: Context<UpdateFieldLabeled>,
+        label: String,
+        data: UpdateFieldData,
+    ) -> Result<()> {
+        check_label(&label)?;
+        process_update_field(
+            &ctx.accounts.metadata,
+            Some(&ctx.accounts.mint),
+            ctx.accounts.update_authority.key,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.instructions.as_deref(),
+            None,
+            ctx.accounts.fee_destination.as_deref(),
+            data,
+        )
+    }
+
+    pub fn migrate_pda(ctx: Context<MigratePda>) -> Result<()> {
+        // Get TokenMetadata stored at the old address.
+        let old_metadata_info = ctx.accounts.old_metadata.to_account_info();
+        let token_metadata = load_token_metadata(&old_metadata_info)?;
+
+        // Check the old account belongs to this mint.
+        if token_metadata.mint != ctx.accounts.mint.key() {
+            return Err(ProgramError::Custom(TokenMetadataError::IncorrectAccount as u32).into());
+        }
+
+        // Check update authority.
+        check_update_authority(
+            &token_metadata,
+            ctx.accounts.update_authority.key,
+            ctx.accounts.instructions.as_deref(),
+        )?;
 
-        // Create metadata account
+        // Create metadata account at the canonical PDA with the same size
+        let size = old_metadata_info.data_len();
+        let lamports = Rent::get()?.minimum_balance(size);
         let mint = ctx.accounts.mint.key();
         let signer_seeds: &[&[&[u8]]] = &[&[b"metadata", mint.as_ref(), &[ctx.bumps.metadata]]];
         create_account(
@@ -90,151 +623,3705 @@ pub mod token_metadata {
             &id(),
         )?;
 
-        // Initialize metadata account data
-        let instance_size = get_instance_packed_len(&token_metadata)?;
-        let mut buffer = ctx.accounts.metadata.try_borrow_mut_data()?;
-        let mut state = TlvStateMut::unpack(&mut buffer)?;
-        state.alloc::<TokenMetadata>(instance_size, false)?;
-        state.pack_first_variable_len_value(&token_metadata)?;
-        Ok(())
+        // Copy the packed TokenMetadata as is.
+        ctx.accounts
+            .metadata
+            .try_borrow_mut_data()?
+            .copy_from_slice(&old_metadata_info.try_borrow_data()?);
+
+        // Close the old account, returning rent to the payer.
+        msg!("Closing old metadata account {}", old_metadata_info.key);
+        close_account(&old_metadata_info, &ctx.accounts.payer.to_account_info())
     }
 
-    pub fn update_field(ctx: Context<UpdateField>, data: UpdateFieldData) -> Result<()> {
-        // Get current TokenMetadata.
-        let mut token_metadata = {
-            let buffer = ctx.accounts.metadata.try_borrow_data()?;
-            let state = TlvStateBorrowed::unpack(&buffer)?;
-            state.get_first_variable_len_value::<TokenMetadata>()?
-        };
+    pub fn swap_keys(ctx: Context<UpdateMetadata>, key_a: String, key_b: String) -> Result<()> {
+        check_key_not_reserved(&key_a)?;
+        check_key_not_reserved(&key_b)?;
 
-        // Check update authority.
-        let update_authority = Option::<Pubkey>::from(token_metadata.update_authority)
-            .ok_or_else(|| ProgramError::Custom(TokenMetadataError::ImmutableMetadata as u32))?;
-        msg!("Update authority: {:?}", update_authority);
-        if update_authority != *ctx.accounts.update_authority.key {
-            return Err(
-                ProgramError::Custom(TokenMetadataError::IncorrectUpdateAuthority as u32).into(),
+        apply_update(ctx.accounts, |token_metadata| {
+            check_field_unlocked(token_metadata, &AnchorField::Key(key_a.clone()))?;
+            check_field_unlocked(token_metadata, &AnchorField::Key(key_b.clone()))?;
+
+            // Both keys must exist
+            let value_a = get_key_value(token_metadata, &key_a)?;
+            let value_b = get_key_value(token_metadata, &key_b)?;
+            token_metadata.update(Field::Key(key_a), value_b);
+            token_metadata.update(Field::Key(key_b), value_a);
+            Ok(())
+        })
+    }
+
+    pub fn rename_key(ctx: Context<UpdateMetadata>, old: String, new: String) -> Result<()> {
+        check_key_not_reserved(&old)?;
+        check_key_not_reserved(&new)?;
+        check_key_not_core_field(&new)?;
+
+        apply_update(ctx.accounts, |token_metadata| {
+            check_field_unlocked(token_metadata, &AnchorField::Key(old.clone()))?;
+            check_field_unlocked(token_metadata, &AnchorField::Key(new.clone()))?;
+
+            // Move the value, the new key must not overwrite an existing one
+            let value = get_key_value(token_metadata, &old)?;
+            require!(
+                find_key_value(token_metadata, &new).is_none(),
+                MetadataError::DuplicateKey
+            );
+            token_metadata.remove_key(&old);
+            token_metadata.update(Field::Key(new), value);
+            Ok(())
+        })
+    }
+
+    pub fn set_key_if_absent(
+        ctx: Context<UpdateMetadata>,
+        key: String,
+        value: String,
+    ) -> Result<()> {
+        check_key_not_reserved(&key)?;
+        check_key_not_core_field(&key)?;
+        require!(value.len() <= MAX_VALUE_LEN, MetadataError::ValueTooLong);
+
+        apply_update(ctx.accounts, |token_metadata| {
+            check_field_unlocked(token_metadata, &AnchorField::Key(key.clone()))?;
+            // Write once, an existing value is never replaced
+            require!(
+                find_key_value(token_metadata, &key).is_none(),
+                MetadataError::KeyAlreadyExists
             );
+            token_metadata.update(Field::Key(key), value);
+            Ok(())
+        })
+    }
+
+    pub fn deprecate_key(ctx: Context<UpdateMetadata>, key: String) -> Result<()> {
+        check_key_not_reserved(&key)?;
+
+        apply_update(ctx.accounts, |token_metadata| {
+            check_field_unlocked(token_metadata, &AnchorField::Key(key.clone()))?;
+            // Only existing keys can be deprecated
+            get_key_value(token_metadata, &key)?;
+            token_metadata.update(Field::Key(key), TOMBSTONE.to_string());
+            Ok(())
+        })
+    }
+
+    pub fn move_key(ctx: Context<MoveKey>, key: String) -> Result<()> {
+        check_key_not_reserved(&key)?;
+        check_key_not_core_field(&key)?;
+        if ctx.accounts.source_mint.key() == ctx.accounts.destination_mint.key() {
+            return Err(ProgramError::Custom(TokenMetadataError::IncorrectAccount as u32).into());
         }
 
-        // Perform the update on the TokenMetadata.
-        let field = data.field.to_field();
-        token_metadata.update(field, data.value);
-        msg!("TokenMetadata: {:?}", token_metadata);
+        let mut source = load_token_metadata(&ctx.accounts.source_metadata)?;
+        check_update_authority(
+            &source,
+            ctx.accounts.source_authority.key,
+            ctx.accounts.instructions.as_deref(),
+        )?;
+        let mut destination = load_token_metadata(&ctx.accounts.destination_metadata)?;
+        check_update_authority(
+            &destination,
+            ctx.accounts.destination_authority.key,
+            ctx.accounts.instructions.as_deref(),
+        )?;
 
-        // Calculate the required size and lamports for the updated metadata.
-        let new_size = TokenMetadata::tlv_size_of(&token_metadata)?;
-        let required_lamports = Rent::get()?.minimum_balance(new_size as usize);
-
-        // Get current state of the metadata account.
-        let metadata_account_info = ctx.accounts.metadata.to_account_info();
-        let current_lamports = metadata_account_info.lamports();
-
-        // Transfer lamports if required.
-        if required_lamports != current_lamports {
-            let lamport_difference =
-                (required_lamports as isize - current_lamports as isize).abs() as u64;
-            if required_lamports > current_lamports {
-                // Transfer additional lamports to metadata account.
-                msg!(
-                    "Transferring {} lamports to metadata account",
-                    lamport_difference
-                );
-                transfer(
-                    CpiContext::new(
-                        ctx.accounts.system_program.to_account_info(),
-                        Transfer {
-                            from: ctx.accounts.payer.to_account_info(),
-                            to: metadata_account_info.clone(),
-                        },
-                    ),
-                    lamport_difference,
-                )?;
-            } else {
-                // Transfer excess lamports back to payer.
-                msg!("Transferring {} lamports back to payer", lamport_difference);
-                // Modify lamports directly because metadata account is owned by this program (and not System Program)
-                ctx.accounts.metadata.sub_lamports(lamport_difference)?;
-                ctx.accounts.payer.add_lamports(lamport_difference)?;
+        let field = AnchorField::Key(key.clone());
+        check_field_unlocked(&source, &field)?;
+        check_field_unlocked(&destination, &field)?;
+
+        // Don't overwrite a value already on the destination
+        let value = get_key_value(&source, &key)?;
+        require!(
+            find_key_value(&destination, &key).is_none(),
+            MetadataError::DuplicateKey
+        );
+        source.remove_key(&key);
+        destination.update(Field::Key(key), value);
+        finalize_metadata(&mut source)?;
+        finalize_metadata(&mut destination)?;
+
+        // Settle the rent of both accounts with the payer
+        let system_program = ctx.accounts.system_program.to_account_info();
+        realloc_metadata(
+            &ctx.accounts.source_metadata,
+            &ctx.accounts.payer,
+            &system_program,
+            &source,
+            false,
+        )?;
+        realloc_metadata(
+            &ctx.accounts.destination_metadata,
+            &ctx.accounts.payer,
+            &system_program,
+            &destination,
+            false,
+        )
+    }
+
+    pub fn remap_keys(
+        ctx: Context<UpdateMetadata>,
+        prefix_old: String,
+        prefix_new: String,
+    ) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            // Reserved keys are never remapped
+            let matches =
+                |key: &str| key.starts_with(&prefix_old) && !key.starts_with(RESERVED_KEY_PREFIX);
+            let count = token_metadata
+                .additional_metadata
+                .iter()
+                .filter(|(key, _)| matches(key))
+                .count();
+            require!(count <= MAX_REMAP_KEYS, MetadataError::TooManyKeys);
+
+            let mut remapped = Vec::with_capacity(token_metadata.additional_metadata.len());
+            for (key, value) in &token_metadata.additional_metadata {
+                if !matches(key) {
+                    remapped.push((key.clone(), value.clone()));
+                    continue;
+                }
+                let new_key = format!("{}{}", prefix_new, &key[prefix_old.len()..]);
+                check_key_not_reserved(&new_key)?;
+                check_key_not_core_field(&new_key)?;
+                check_field_unlocked(token_metadata, &AnchorField::Key(key.clone()))?;
+                check_field_unlocked(token_metadata, &AnchorField::Key(new_key.clone()))?;
+
+                // Don't overwrite a key that isn't remapped itself
+                let collides = token_metadata
+                    .additional_metadata
+                    .iter()
+                    .any(|(other, _)| *other == new_key && !matches(other));
+                require!(!collides, MetadataError::DuplicateKey);
+                remapped.push((new_key, value.clone()));
             }
-        }
+            msg!("Remapped {} keys", count);
+            token_metadata.additional_metadata = remapped;
+            Ok(())
+        })
+    }
+
+    pub fn set_oracle(ctx: Context<UpdateMetadata>, oracle: Option<Pubkey>) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            // Rotate or remove the oracle
+            match oracle {
+                Some(oracle) => {
+                    token_metadata.update(Field::Key(ORACLE_KEY.to_string()), oracle.to_string())
+                }
+                None => {
+                    token_metadata.remove_key(ORACLE_KEY);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    pub fn set_delegate(
+        ctx: Context<UpdateMetadata>,
+        delegate: Option<Pubkey>,
+        hold_refunds: bool,
+    ) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.remove_key(DELEGATE_HOLD_REFUNDS_KEY);
+            token_metadata.remove_key(DELEGATE_PROGRAM_KEY);
+            match delegate {
+                Some(delegate) => {
+                    token_metadata
+                        .update(Field::Key(DELEGATE_KEY.to_string()), delegate.to_string());
+                    if hold_refunds {
+                        token_metadata.update(
+                            Field::Key(DELEGATE_HOLD_REFUNDS_KEY.to_string()),
+                            true.to_string(),
+                        );
+                    }
+                }
+                None => {
+                    token_metadata.remove_key(DELEGATE_KEY);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    pub fn set_program_delegate(
+        ctx: Context<UpdateMetadata>,
+        program_id: Pubkey,
+        seeds: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        // Derived here so the delegate is off the curve, only the program can sign for it
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        let (delegate, _) = Pubkey::try_find_program_address(&seeds, &program_id)
+            .ok_or(ProgramError::InvalidSeeds)?;
+        msg!("Delegate: {}", delegate);
+
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.remove_key(DELEGATE_HOLD_REFUNDS_KEY);
+            token_metadata.update(Field::Key(DELEGATE_KEY.to_string()), delegate.to_string());
+            token_metadata.update(
+                Field::Key(DELEGATE_PROGRAM_KEY.to_string()),
+                program_id.to_string(),
+            );
+            Ok(())
+        })
+    }
 
-        // Reallocate and update the metadata account data.
-        realloc_and_pack_first_variable_len(
-            &ctx.accounts.metadata.to_account_info(),
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        check_update_authority(
             &token_metadata,
+            ctx.accounts.update_authority.key,
+            ctx.accounts.instructions.as_deref(),
         )?;
+
+        // Everything above the rent exempt minimum was held from a delegate's shrink
+        let metadata = &ctx.accounts.metadata;
+        let required_lamports = Rent::get()?.minimum_balance(metadata.data_len());
+        let held = metadata.lamports().saturating_sub(required_lamports);
+        msg!("Claiming {} lamports", held);
+        metadata.sub_lamports(held)?;
+        ctx.accounts.update_authority.add_lamports(held)?;
         Ok(())
     }
-}
 
-// Order of the accounts in the struct matters
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    /// CHECK: Create this account in instruction
-    #[account(
-        seeds = [b"metadata", mint.key().as_ref()], 
-        bump)
-    ]
-    pub metadata: UncheckedAccount<'info>,
-    /// CHECK: Optional update authority, unchecked because it can either be SystemAccount or a PDA owned by another program
-    pub update_authority: Option<UncheckedAccount<'info>>,
-    pub mint: InterfaceAccount<'info, Mint>,
-    pub mint_authority: Signer<'info>,
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn rebalance_rent(ctx: Context<RebalanceRent>) -> Result<()> {
+        let authority = ctx.accounts.update_authority.key;
+        check_update_authority(
+            &load_token_metadata(&ctx.accounts.source_metadata)?,
+            authority,
+            ctx.accounts.instructions.as_deref(),
+        )?;
+        check_update_authority(
+            &load_token_metadata(&ctx.accounts.destination_metadata)?,
+            authority,
+            ctx.accounts.instructions.as_deref(),
+        )?;
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct InitializeData {
-    pub name: String,
-    pub symbol: String,
-    pub uri: String,
-}
+        // Move everything above the source's rent exempt minimum, the destination only gains
+        let source = &ctx.accounts.source_metadata;
+        let required_lamports = Rent::get()?.minimum_balance(source.data_len());
+        let surplus = source.lamports().saturating_sub(required_lamports);
+        msg!("Moving {} lamports", surplus);
+        source.sub_lamports(surplus)?;
+        ctx.accounts.destination_metadata.add_lamports(surplus)?;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct UpdateField<'info> {
-    /// CHECK: check by address only, no anchor type to check against
-    #[account(
-        seeds = [b"metadata", mint.key().as_ref()],
-        bump)
-    ]
-    pub metadata: UncheckedAccount<'info>,
-    pub update_authority: Signer<'info>,
-    pub mint: InterfaceAccount<'info, Mint>,
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn set_royalties(
+        ctx: Context<UpdateMetadata>,
+        seller_fee_basis_points: u16,
+        creators: Vec<Creator>,
+    ) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            write_royalties(token_metadata, seller_fee_basis_points, &creators)
+        })
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct UpdateFieldData {
-    /// Field to update in the metadata
-    pub field: AnchorField,
-    /// Value to write for the field
-    pub value: String,
-}
+    pub fn export_metaplex(ctx: Context<ReadMetadata>) -> Result<MetaplexData> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        to_metaplex(&token_metadata)
+    }
 
-// Need to do this so the enum shows up in the IDL
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub enum AnchorField {
-    /// The name field, corresponding to `TokenMetadata.name`
-    Name,
-    /// The symbol field, corresponding to `TokenMetadata.symbol`
-    Symbol,
-    /// The uri field, corresponding to `TokenMetadata.uri`
-    Uri,
-    /// A user field, whose key is given by the associated string
-    Key(String),
-}
+    pub fn set_max_supply(ctx: Context<UpdateMetadata>, max_supply: u64) -> Result<()> {
+        // Informational only, but it can't already be exceeded
+        require!(
+            max_supply >= ctx.accounts.mint.supply,
+            MetadataError::MaxSupplyBelowSupply
+        );
 
-// Convert AnchorField to Field from spl_token_metadata_interface
-impl AnchorField {
-    fn to_field(&self) -> Field {
-        match self {
-            AnchorField::Name => Field::Name,
-            AnchorField::Symbol => Field::Symbol,
-            AnchorField::Uri => Field::Uri,
-            AnchorField::Key(s) => Field::Key(s.clone()),
-        }
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.update(
+                Field::Key(MAX_SUPPLY_KEY.to_string()),
+                max_supply.to_string(),
+            );
+            Ok(())
+        })
+    }
+
+    pub fn get_last_updater(ctx: Context<ReadMetadata>) -> Result<Option<Pubkey>> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        let last_updater = find_key_value(&token_metadata, LAST_UPDATER_KEY)
+            .map(Pubkey::from_str)
+            .transpose()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(last_updater)
+    }
+
+    pub fn get_max_supply(ctx: Context<ReadMetadata>) -> Result<Option<u64>> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        let max_supply = find_key_value(&token_metadata, MAX_SUPPLY_KEY)
+            .map(u64::from_str)
+            .transpose()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(max_supply)
+    }
+
+    pub fn set_flag(ctx: Context<UpdateMetadata>, flag: MetadataFlag, value: bool) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            let flags = if value {
+                read_flags(token_metadata)? | flag.bit()
+            } else {
+                read_flags(token_metadata)? & !flag.bit()
+            };
+            // No key at all once every flag is cleared
+            if flags == 0 {
+                token_metadata.remove_key(FLAGS_KEY);
+            } else {
+                token_metadata.update(Field::Key(FLAGS_KEY.to_string()), flags.to_string());
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get_flags(ctx: Context<ReadMetadata>) -> Result<u32> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        read_flags(&token_metadata)
+    }
+
+    pub fn set_uri_content_type(ctx: Context<UpdateMetadata>, mime: String) -> Result<()> {
+        check_mime_type(&mime)?;
+
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.update(Field::Key(URI_CONTENT_TYPE_KEY.to_string()), mime);
+            Ok(())
+        })
+    }
+
+    pub fn get_uri_content_type(ctx: Context<ReadMetadata>) -> Result<Option<String>> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        Ok(find_key_value(&token_metadata, URI_CONTENT_TYPE_KEY).map(str::to_string))
+    }
+
+    pub fn set_fallback_uri(ctx: Context<UpdateMetadata>, uri: String) -> Result<()> {
+        check_uri(&uri)?;
+
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.update(Field::Key(URI_FALLBACK_KEY.to_string()), uri);
+            Ok(())
+        })
+    }
+
+    pub fn get_uris(ctx: Context<ReadMetadata>) -> Result<Uris> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        Ok(Uris {
+            fallback_uri: find_key_value(&token_metadata, URI_FALLBACK_KEY).map(str::to_string),
+            uri: token_metadata.uri,
+        })
+    }
+
+    pub fn set_json_attributes(ctx: Context<UpdateMetadata>, json: String) -> Result<()> {
+        check_json(&json)?;
+
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.update(Field::Key(ATTRIBUTES_KEY.to_string()), json);
+            Ok(())
+        })
+    }
+
+    pub fn get_json_attributes(ctx: Context<ReadMetadata>) -> Result<Option<String>> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        Ok(find_key_value(&token_metadata, ATTRIBUTES_KEY).map(str::to_string))
+    }
+
+    pub fn set_display(
+        ctx: Context<UpdateMetadata>,
+        decimals_override: u8,
+        prefix: String,
+        suffix: String,
+    ) -> Result<()> {
+        // Wallets can show fewer decimals than the mint has, but not more
+        require!(
+            decimals_override <= ctx.accounts.mint.decimals,
+            MetadataError::InvalidDisplayDecimals
+        );
+
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.update(
+                Field::Key(DISPLAY_DECIMALS_KEY.to_string()),
+                decimals_override.to_string(),
+            );
+            token_metadata.update(Field::Key(DISPLAY_PREFIX_KEY.to_string()), prefix);
+            token_metadata.update(Field::Key(DISPLAY_SUFFIX_KEY.to_string()), suffix);
+            Ok(())
+        })
+    }
+
+    pub fn get_display(ctx: Context<ReadMetadata>) -> Result<DisplayConfig> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        let decimals_override = find_key_value(&token_metadata, DISPLAY_DECIMALS_KEY)
+            .map(u8::from_str)
+            .transpose()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(DisplayConfig {
+            decimals_override,
+            prefix: find_key_value(&token_metadata, DISPLAY_PREFIX_KEY)
+                .unwrap_or_default()
+                .to_string(),
+            suffix: find_key_value(&token_metadata, DISPLAY_SUFFIX_KEY)
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    pub fn set_localized(
+        ctx: Context<UpdateMetadata>,
+        locale: String,
+        name: String,
+        symbol: String,
+    ) -> Result<()> {
+        check_locale(&locale)?;
+        require!(
+            name.len() <= MAX_VALUE_LEN && symbol.len() <= MAX_VALUE_LEN,
+            MetadataError::ValueTooLong
+        );
+
+        apply_update(ctx.accounts, |token_metadata| {
+            let (name_key, symbol_key) = localized_keys(&locale);
+            token_metadata.update(Field::Key(name_key), name);
+            token_metadata.update(Field::Key(symbol_key), symbol);
+            Ok(())
+        })
+    }
+
+    pub fn get_localized(ctx: Context<ReadMetadata>, locale: String) -> Result<LocalizedNames> {
+        check_locale(&locale)?;
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+
+        // Each field falls back to the default if the locale has no variant
+        let (name_key, symbol_key) = localized_keys(&locale);
+        Ok(LocalizedNames {
+            name: find_key_value(&token_metadata, &name_key)
+                .unwrap_or(&token_metadata.name)
+                .to_string(),
+            symbol: find_key_value(&token_metadata, &symbol_key)
+                .unwrap_or(&token_metadata.symbol)
+                .to_string(),
+        })
+    }
+
+    pub fn reset_metadata(ctx: Context<UpdateMetadata>, confirmation: Pubkey) -> Result<()> {
+        // Deliberate friction, the caller has to repeat the mint address
+        require_keys_eq!(
+            confirmation,
+            ctx.accounts.mint.key(),
+            MetadataError::InvalidConfirmation
+        );
+
+        apply_update(ctx.accounts, |token_metadata| {
+            // Locked fields can't be reset
+            for field in [AnchorField::Name, AnchorField::Symbol, AnchorField::Uri] {
+                check_field_unlocked(token_metadata, &field)?;
+            }
+            for (key, _) in &token_metadata.additional_metadata {
+                if !key.starts_with(RESERVED_KEY_PREFIX) {
+                    check_field_unlocked(token_metadata, &AnchorField::Key(key.clone()))?;
+                }
+            }
+
+            token_metadata.name = String::new();
+            token_metadata.symbol = String::new();
+            token_metadata.uri = String::new();
+            // Reserved keys hold policies and are kept
+            token_metadata
+                .additional_metadata
+                .retain(|(key, _)| key.starts_with(RESERVED_KEY_PREFIX));
+            Ok(())
+        })
+    }
+
+    pub fn clear_custom_keys(ctx: Context<UpdateMetadata>) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            for (key, _) in &token_metadata.additional_metadata {
+                if !key.starts_with(RESERVED_KEY_PREFIX) {
+                    check_field_unlocked(token_metadata, &AnchorField::Key(key.clone()))?;
+                }
+            }
+
+            // Core fields and the reserved keys holding policies are kept,
+            // the shrunk account refunds the payer in a single realloc
+            token_metadata
+                .additional_metadata
+                .retain(|(key, _)| key.starts_with(RESERVED_KEY_PREFIX));
+            Ok(())
+        })
+    }
+
+    pub fn lock_field(
+        ctx: Context<UpdateMetadata>,
+        field: AnchorField,
+        permanent: bool,
+    ) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            policy::lock_field(token_metadata, &field, permanent)
+        })
+    }
+
+    pub fn unlock_field(ctx: Context<UpdateMetadata>, field: AnchorField) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            policy::unlock_field(token_metadata, &field)
+        })
+    }
+
+    pub fn commit_field(
+        ctx: Context<UpdateMetadata>,
+        field: AnchorField,
+        hash: [u8; 32],
+    ) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            token_metadata.update(
+                Field::Key(commit_key(&field)),
+                Hash::new_from_array(hash).to_string(),
+            );
+            Ok(())
+        })
+    }
+
+    pub fn reveal_field(
+        ctx: Context<UpdateMetadata>,
+        field: AnchorField,
+        value: String,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        if let AnchorField::Key(key) = &field {
+            check_key_not_reserved(key)?;
+            check_key_not_core_field(key)?;
+        }
+        require!(value.len() <= MAX_VALUE_LEN, MetadataError::ValueTooLong);
+
+        apply_update(ctx.accounts, |token_metadata| {
+            check_field_unlocked(token_metadata, &field)?;
+
+            // The value must hash to the commitment, the salt keeps guesses from being checked
+            let key = commit_key(&field);
+            let commitment =
+                find_key_value(token_metadata, &key).ok_or(MetadataError::NoCommitment)?;
+            if hashv(&[value.as_bytes(), &salt]).to_string() != commitment {
+                return err!(MetadataError::CommitmentMismatch);
+            }
+
+            token_metadata.remove_key(&key);
+            token_metadata.update(field.to_field(), value);
+            Ok(())
+        })
+    }
+
+    pub fn hide_field(
+        ctx: Context<UpdateMetadata>,
+        field: AnchorField,
+        placeholder: String,
+    ) -> Result<()> {
+        if let AnchorField::Key(key) = &field {
+            check_key_not_reserved(key)?;
+            check_key_not_core_field(key)?;
+        }
+        require!(
+            placeholder.len() <= MAX_VALUE_LEN,
+            MetadataError::ValueTooLong
+        );
+
+        apply_update(ctx.accounts, |token_metadata| {
+            require!(
+                find_key_value(token_metadata, REVEALED_KEY).is_none(),
+                MetadataError::AlreadyRevealed
+            );
+            token_metadata.update(Field::Key(hidden_key(&field)), placeholder);
+            Ok(())
+        })
+    }
+
+    pub fn reveal(ctx: Context<UpdateMetadata>) -> Result<()> {
+        apply_update(ctx.accounts, |token_metadata| {
+            require!(
+                find_key_value(token_metadata, REVEALED_KEY).is_none(),
+                MetadataError::AlreadyRevealed
+            );
+            token_metadata.update(Field::Key(REVEALED_KEY.to_string()), String::new());
+            Ok(())
+        })
+    }
+
+    pub fn claim_authority(ctx: Context<ClaimAuthority>) -> Result<()> {
+        let mut token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+
+        // Immutable metadata stays immutable, even for a claimant nominated before
+        if Option::<Pubkey>::from(token_metadata.update_authority).is_none() {
+            return Err(ProgramError::Custom(TokenMetadataError::ImmutableMetadata as u32).into());
+        }
+
+        // Check claimant
+        let claimant = find_key_value(&token_metadata, CLAIMANT_KEY)
+            .and_then(|claimant| Pubkey::from_str(claimant).ok())
+            .ok_or(MetadataError::NoPendingClaim)?;
+        require_keys_eq!(
+            claimant,
+            ctx.accounts.claimant.key(),
+            MetadataError::IncorrectClaimant
+        );
+
+        // One time claim, the claimant becomes the update authority
+        token_metadata.remove_key(CLAIMANT_KEY);
+        token_metadata.update_authority = OptionalNonZeroPubkey::try_from(Some(claimant))
+            .map_err(|_| ProgramError::InvalidArgument)?;
+        finalize_metadata(&mut token_metadata)?;
+        msg!("TokenMetadata: {:?}", token_metadata);
+
+        realloc_metadata(
+            &ctx.accounts.metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            &token_metadata,
+            false,
+        )
+    }
+
+    pub fn update_authority(
+        ctx: Context<UpdateAuthority>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let mut token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+
+        // Immutable metadata stays immutable, even for the admin
+        if Option::<Pubkey>::from(token_metadata.update_authority).is_none() {
+            return Err(ProgramError::Custom(TokenMetadataError::ImmutableMetadata as u32).into());
+        }
+        // Only the admin rotates the update authority, if one is set
+        let signer = ctx.accounts.authority.key;
+        match find_key_value(&token_metadata, ADMIN_KEY) {
+            Some(admin) => {
+                require!(admin == signer.to_string(), MetadataError::NotAdmin);
+                check_authority_program(&token_metadata, ctx.accounts.instructions.as_deref())?;
+            }
+            None => check_update_authority(
+                &token_metadata,
+                signer,
+                ctx.accounts.instructions.as_deref(),
+            )?,
+        }
+
+        // Reject instead of silently making the metadata immutable
+        if new_authority == Some(Pubkey::default()) {
+            return err!(MetadataError::InvalidUpdateAuthority);
+        }
+        // The balance is only known from the account, which must match the new authority
+        if let Some(new_authority) = new_authority {
+            if let Some(account) = ctx.accounts.new_authority.as_ref() {
+                if *account.key != new_authority {
+                    return Err(
+                        ProgramError::Custom(TokenMetadataError::IncorrectAccount as u32).into(),
+                    );
+                }
+                check_authority_funded(&token_metadata, account)?;
+            } else if find_key_value(&token_metadata, REQUIRE_FUNDED_AUTHORITY_KEY).is_some() {
+                return Err(ProgramError::NotEnoughAccountKeys.into());
+            }
+        }
+        token_metadata.update_authority = OptionalNonZeroPubkey::try_from(new_authority)
+            .map_err(|_| ProgramError::InvalidArgument)?;
+        // A pending claim can't outlive the authority it would take over
+        if new_authority.is_none() {
+            token_metadata.remove_key(CLAIMANT_KEY);
+        }
+        finalize_metadata(&mut token_metadata)?;
+        msg!("TokenMetadata: {:?}", token_metadata);
+
+        realloc_metadata(
+            &ctx.accounts.metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            &token_metadata,
+            false,
+        )
+    }
+
+    pub fn oracle_set_field(
+        ctx: Context<OracleSetField>,
+        key: String,
+        value: String,
+    ) -> Result<()> {
+        require!(value.len() <= MAX_VALUE_LEN, MetadataError::ValueTooLong);
+        let mut token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+
+        // Check oracle
+        let oracle = find_key_value(&token_metadata, ORACLE_KEY)
+            .and_then(|oracle| Pubkey::from_str(oracle).ok())
+            .ok_or(MetadataError::OracleNotSet)?;
+        require_keys_eq!(
+            oracle,
+            ctx.accounts.oracle.key(),
+            MetadataError::IncorrectOracle
+        );
+
+        // Oracle keys are namespaced so they can't be written through update_field
+        token_metadata.update(Field::Key(format!("{}{}", ORACLE_KEY_PREFIX, key)), value);
+        finalize_metadata(&mut token_metadata)?;
+        msg!("TokenMetadata: {:?}", token_metadata);
+
+        realloc_metadata(
+            &ctx.accounts.metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            &token_metadata,
+            false,
+        )
+    }
+
+    pub fn emit_compact(ctx: Context<ReadMetadata>) -> Result<()> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+
+        // Only the core fields, each prefixed with its u16 length
+        let mut compact = Vec::new();
+        for field in [
+            &token_metadata.name,
+            &token_metadata.symbol,
+            &token_metadata.uri,
+        ] {
+            let len = u16::try_from(field.len()).map_err(|_| ProgramError::InvalidAccountData)?;
+            compact.extend_from_slice(&len.to_le_bytes());
+            compact.extend_from_slice(field.as_bytes());
+        }
+        set_return_data(&compact);
+        Ok(())
+    }
+
+    pub fn get_raw_tlv(ctx: Context<ReadMetadata>, start: u32, len: u32) -> Result<()> {
+        // Raw bytes without deserializing, to debug accounts that fail normal reads.
+        // Not masked, hidden values are in the account data for anyone to fetch anyway
+        let buffer = ctx.accounts.metadata.try_borrow_data()?;
+        let start = start as usize;
+        let end = start
+            .checked_add(len as usize)
+            .filter(|end| *end <= buffer.len() && len as usize <= MAX_RETURN_DATA)
+            .ok_or(MetadataError::RangeOutOfBounds)?;
+        set_return_data(&buffer[start..end]);
+        Ok(())
+    }
+
+    pub fn get_metadata(ctx: Context<ReadMetadata>) -> Result<MetadataView> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        Ok(MetadataView::from(token_metadata))
+    }
+
+    pub fn get_metadata_labeled(
+        ctx: Context<ReadMetadataLabeled>,
+        label: String,
+    ) -> Result<MetadataView> {
+        check_label(&label)?;
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        Ok(MetadataView::from(token_metadata))
+    }
+
+    pub fn get_keys_labeled(
+        ctx: Context<ReadMetadataLabeled>,
+        label: String,
+        keys: Vec<String>,
+    ) -> Result<()> {
+        check_label(&label)?;
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        return_key_values(&token_metadata, &keys)
+    }
+
+    pub fn get_metadata_filtered(
+        ctx: Context<ReadMetadata>,
+        hide_deprecated: bool,
+    ) -> Result<MetadataView> {
+        let mut token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        if hide_deprecated {
+            token_metadata
+                .additional_metadata
+                .retain(|(_, value)| value != TOMBSTONE);
+        }
+        Ok(MetadataView::from(token_metadata))
+    }
+
+    pub fn get_metadata_page(
+        ctx: Context<ReadMetadata>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<MetadataPage> {
+        require!(limit as usize <= MAX_PAGE_KEYS, MetadataError::TooManyKeys);
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+
+        // Paged in the sorted order of get_metadata, so pages don't overlap
+        let mut metadata = MetadataView::from(token_metadata);
+        let total = metadata.additional_metadata.len() as u32;
+        metadata.additional_metadata = metadata
+            .additional_metadata
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+        Ok(MetadataPage { metadata, total })
+    }
+
+    pub fn get_metadata_with_supply(ctx: Context<ReadMetadata>) -> Result<MetadataWithSupply> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        Ok(MetadataWithSupply {
+            metadata: MetadataView::from(token_metadata),
+            supply: ctx.accounts.mint.supply,
+            decimals: ctx.accounts.mint.decimals,
+        })
+    }
+
+    pub fn get_metadata_if_newer(
+        ctx: Context<ReadMetadata>,
+        since_slot: u64,
+    ) -> Result<Option<MetadataView>> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+
+        // None means not modified, untracked metadata is always returned
+        let updated_slot = find_key_value(&token_metadata, UPDATED_SLOT_KEY)
+            .map(u64::from_str)
+            .transpose()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if matches!(updated_slot, Some(slot) if slot < since_slot) {
+            return Ok(None);
+        }
+        Ok(Some(MetadataView::from(token_metadata)))
+    }
+
+    pub fn has_key(ctx: Context<ReadMetadata>, key: String) -> Result<bool> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        Ok(find_key_value(&token_metadata, &key).is_some())
+    }
+
+    pub fn get_keys(ctx: Context<ReadMetadata>, keys: Vec<String>) -> Result<()> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+        return_key_values(&token_metadata, &keys)
+    }
+
+    pub fn get_rent_info(ctx: Context<ReadMetadata>) -> Result<RentInfo> {
+        check_metadata_initialized(&ctx.accounts.metadata)?;
+        let size = ctx.accounts.metadata.data_len();
+        Ok(RentInfo {
+            size: size as u64,
+            rent_exempt_minimum: Rent::get()?.minimum_balance(size),
+            lamports: ctx.accounts.metadata.lamports(),
+        })
+    }
+
+    pub fn get_size(ctx: Context<ReadMetadata>) -> Result<MetadataSize> {
+        check_metadata_initialized(&ctx.accounts.metadata)?;
+        // The TLV length follows the 8 byte discriminator, no need to deserialize the value
+        let data = ctx.accounts.metadata.try_borrow_data()?;
+        let value_len = data
+            .get(8..12)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(MetadataSize {
+            data_len: data.len() as u64,
+            value_len,
+        })
+    }
+
+    pub fn export_leaf(ctx: Context<ReadMetadata>) -> Result<[u8; 32]> {
+        let token_metadata = load_masked_metadata(&ctx.accounts.metadata)?;
+
+        // sha256(0x00 || mint || update_authority || sha256(name) || sha256(symbol)
+        //     || sha256(uri) || sha256(borsh(additional_metadata sorted by key, without __integrity)))
+        // The 0x00 prefix separates leaves from the interior nodes of the tree
+        let mut additional_metadata = token_metadata.additional_metadata.clone();
+        additional_metadata.retain(|(key, _)| key != INTEGRITY_KEY);
+        additional_metadata.sort();
+        let additional_metadata = additional_metadata
+            .try_to_vec()
+            .map_err(|_| MetadataError::MetadataSerializationFailed)?;
+
+        // No update authority hashes as the zeroed pubkey, as it is stored
+        let update_authority =
+            Option::<Pubkey>::from(token_metadata.update_authority).unwrap_or_default();
+        let leaf = hashv(&[
+            &[0],
+            token_metadata.mint.as_ref(),
+            update_authority.as_ref(),
+            hash(token_metadata.name.as_bytes()).as_ref(),
+            hash(token_metadata.symbol.as_bytes()).as_ref(),
+            hash(token_metadata.uri.as_bytes()).as_ref(),
+            hash(&additional_metadata).as_ref(),
+        ]);
+        Ok(leaf.to_bytes())
+    }
+
+    pub fn get_update_authority(ctx: Context<ReadMetadata>) -> Result<Pubkey> {
+        let update_authority = Pubkey::new_from_array(read_packed_key(&ctx.accounts.metadata, 0)?);
+
+        // Default pubkey if the metadata is immutable
+        msg!("Update authority: {}", update_authority);
+        Ok(update_authority)
+    }
+
+    pub fn get_raw_authority(ctx: Context<ReadMetadata>) -> Result<[u8; 32]> {
+        // The OptionalNonZeroPubkey as stored, all zeros if the metadata is immutable
+        read_packed_key(&ctx.accounts.metadata, 0)
+    }
+
+    pub fn diff_metadata(ctx: Context<DiffMetadata>) -> Result<MetadataDiff> {
+        let a = load_masked_metadata(&ctx.accounts.metadata_a)?;
+        let b = load_masked_metadata(&ctx.accounts.metadata_b)?;
+
+        // Keys missing from either side or with different values, in key order,
+        // the integrity hash differs whenever anything else does
+        let mut keys: Vec<&str> = a
+            .additional_metadata
+            .iter()
+            .chain(b.additional_metadata.iter())
+            .map(|(key, _)| key.as_str())
+            .filter(|key| *key != INTEGRITY_KEY)
+            .filter(|key| find_key_value(&a, key) != find_key_value(&b, key))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        // Stop at the return data limit, 3 bools, the truncated flag and the Vec length come first
+        let mut diff = MetadataDiff {
+            name: a.name != b.name,
+            symbol: a.symbol != b.symbol,
+            uri: a.uri != b.uri,
+            keys: Vec::new(),
+            truncated: false,
+        };
+        let mut size = 3 + 1 + 4;
+        for key in keys {
+            size += 4 + key.len();
+            if size > MAX_RETURN_DATA {
+                diff.truncated = true;
+                break;
+            }
+            diff.keys.push(key.to_string());
+        }
+        Ok(diff)
+    }
+
+    pub fn filter_by_symbol(ctx: Context<ReadBatch>, symbol: String) -> Result<Vec<Pubkey>> {
+        require!(
+            ctx.remaining_accounts.len() <= MAX_BATCH_ACCOUNTS,
+            MetadataError::TooManyAccounts
+        );
+
+        let mut mints = Vec::new();
+        for metadata in ctx.remaining_accounts {
+            if metadata.owner != &id() {
+                return Err(
+                    ProgramError::Custom(TokenMetadataError::IncorrectAccount as u32).into(),
+                );
+            }
+            let token_metadata = load_masked_metadata(metadata)?;
+            if token_metadata.symbol == symbol {
+                mints.push(token_metadata.mint);
+            }
+        }
+        msg!("Matched {} mints", mints.len());
+        Ok(mints)
+    }
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        admin: Pubkey,
+        require_approval: bool,
+    ) -> Result<()> {
+        ctx.accounts.config.admin = admin;
+        ctx.accounts.config.require_approval = require_approval;
+        Ok(())
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        admin: Pubkey,
+        require_approval: bool,
+    ) -> Result<()> {
+        ctx.accounts.config.admin = admin;
+        ctx.accounts.config.require_approval = require_approval;
+        Ok(())
+    }
+
+    pub fn approve_mint(ctx: Context<ApproveMint>) -> Result<()> {
+        ctx.accounts.approval.mint = ctx.accounts.mint.key();
+        msg!("Approved mint: {}", ctx.accounts.approval.mint);
+        Ok(())
+    }
+
+    pub fn revoke_mint_approval(ctx: Context<RevokeMintApproval>) -> Result<()> {
+        msg!("Revoked approval for mint: {}", ctx.accounts.approval.mint);
+        Ok(())
+    }
+
+    pub fn initialize_registry(_ctx: Context<InitializeRegistry>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn initialize_change_log(ctx: Context<InitializeChangeLog>) -> Result<()> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        check_update_authority(
+            &token_metadata,
+            ctx.accounts.update_authority.key,
+            ctx.accounts.instructions.as_deref(),
+        )?;
+
+        ctx.accounts.change_log.mint = ctx.accounts.mint.key();
+        Ok(())
+    }
+
+    pub fn get_registry_page(ctx: Context<ReadRegistryPage>, _page: u32) -> Result<Vec<Pubkey>> {
+        Ok(ctx.accounts.registry_page.mints.clone())
+    }
+
+    pub fn create_governance(
+        ctx: Context<CreateGovernance>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            members.len() <= MAX_GOVERNANCE_MEMBERS,
+            MetadataError::TooManyAccounts
+        );
+        // Each member votes once, so the threshold counts unique members
+        require!(
+            !members
+                .iter()
+                .enumerate()
+                .any(|(i, member)| members[..i].contains(member)),
+            MetadataError::DuplicateMember
+        );
+        require!(
+            threshold > 0 && threshold as usize <= members.len(),
+            MetadataError::InvalidThreshold
+        );
+        ctx.accounts.governance.members = members;
+        ctx.accounts.governance.threshold = threshold;
+
+        // Hand the update authority over to the governance
+        let mut token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+        check_update_authority(
+            &token_metadata,
+            ctx.accounts.update_authority.key,
+            ctx.accounts.instructions.as_deref(),
+        )?;
+        token_metadata.update_authority =
+            OptionalNonZeroPubkey::try_from(Some(ctx.accounts.governance.key()))
+                .map_err(|_| ProgramError::InvalidArgument)?;
+        finalize_metadata(&mut token_metadata)?;
+        msg!("TokenMetadata: {:?}", token_metadata);
+
+        realloc_metadata(
+            &ctx.accounts.metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            &token_metadata,
+            false,
+        )
+    }
+
+    pub fn propose_update(
+        ctx: Context<ProposeUpdate>,
+        nonce: u64,
+        data: UpdateFieldData,
+    ) -> Result<()> {
+        let governance = &ctx.accounts.governance;
+        require!(
+            governance.members.contains(ctx.accounts.proposer.key),
+            MetadataError::NotGovernanceMember
+        );
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.version = PROPOSAL_VERSION;
+        proposal.mint = ctx.accounts.mint.key();
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.nonce = nonce;
+        proposal.threshold = governance.threshold;
+        proposal.approvals = Vec::new();
+        proposal.data = data;
+        Ok(())
+    }
+
+    pub fn migrate_proposal(ctx: Context<MigrateProposal>) -> Result<()> {
+        // Proposals written before versioning still carry the `Proposal` discriminator,
+        // a migrated one fails to deserialize so it can't be migrated twice
+        let proposal_info = ctx.accounts.proposal.to_account_info();
+        let proposal = VersionedProposal::from(Proposal::try_deserialize(
+            &mut &proposal_info.try_borrow_data()?[..],
+        )?);
+
+        // Grow the account by the version byte, topping up its rent from the payer
+        let space = VersionedProposal::space(&proposal.data);
+        let lamports = Rent::get()?.minimum_balance(space);
+        let top_up = lamports.saturating_sub(proposal_info.lamports());
+        if top_up > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: proposal_info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        proposal_info.realloc(space, false)?;
+        proposal.try_serialize(&mut &mut proposal_info.try_borrow_mut_data()?[..])?;
+
+        msg!(
+            "Migrated proposal {} to version {}",
+            proposal_info.key,
+            PROPOSAL_VERSION
+        );
+        Ok(())
+    }
+
+    pub fn vote_update(ctx: Context<VoteUpdate>) -> Result<()> {
+        let member = ctx.accounts.member.key();
+        require!(
+            ctx.accounts.governance.members.contains(&member),
+            MetadataError::NotGovernanceMember
+        );
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            !proposal.approvals.contains(&member),
+            MetadataError::AlreadyVoted
+        );
+        proposal.approvals.push(member);
+        msg!(
+            "Approvals: {}/{}",
+            proposal.approvals.len(),
+            proposal.threshold
+        );
+        Ok(())
+    }
+
+    pub fn execute_update(ctx: Context<ExecuteUpdate>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            proposal.approvals.len() >= proposal.threshold as usize,
+            MetadataError::ThresholdNotMet
+        );
+
+        // The governance is the update authority, the approvals stand in for its signature.
+        // The proposal is closed afterwards so it can't be executed twice.
+        process_update_field(
+            &ctx.accounts.metadata,
+            Some(&ctx.accounts.mint),
+            &ctx.accounts.governance.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.instructions.as_deref(),
+            None,
+            ctx.accounts.fee_destination.as_deref(),
+            proposal.data.clone(),
+        )
+    }
+
+    pub fn verify_integrity(ctx: Context<ReadMetadata>) -> Result<bool> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+
+        // Recompute the hash and compare against the stored value.
+        let expected = compute_integrity_hash(&token_metadata)?.to_string();
+        let stored = find_key_value(&token_metadata, INTEGRITY_KEY);
+        msg!("Stored: {:?}, expected: {}", stored, expected);
+        Ok(stored == Some(expected.as_str()))
+    }
+
+    pub fn get_fingerprint(ctx: Context<ReadMetadata>) -> Result<[u8; 8]> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+
+        // First 8 bytes of the integrity hash, which covers every other field
+        let mut fingerprint = [0; 8];
+        fingerprint.copy_from_slice(&compute_integrity_hash(&token_metadata)?.to_bytes()[..8]);
+        Ok(fingerprint)
+    }
+
+    pub fn verify_snapshot(ctx: Context<ReadMetadata>, expected_hash: [u8; 32]) -> Result<()> {
+        let token_metadata = load_token_metadata(&ctx.accounts.metadata)?;
+
+        // Same canonical hash as the integrity check, computed by clients over a cached copy
+        let current = compute_integrity_hash(&token_metadata)?;
+        msg!("Current: {}", current);
+        require!(
+            current.to_bytes() == expected_hash,
+            MetadataError::SnapshotMismatch
+        );
+        Ok(())
+    }
+
+    pub fn repair_size(ctx: Context<RepairSize>) -> Result<()> {
+        let metadata = &ctx.accounts.metadata;
+        check_metadata_initialized(metadata)?;
+
+        // Decode the value after the 8 byte discriminator and 4 byte TLV length,
+        // ignoring the stored length, which is what gets repaired
+        let token_metadata = {
+            let data = metadata.try_borrow_data()?;
+            if data.len() < 12 || data[..8] != *TokenMetadata::SPL_DISCRIMINATOR_SLICE {
+                return err!(MetadataError::MetadataNotInitialized);
+            }
+            TokenMetadata::deserialize(&mut &data[12..])
+                .map_err(|_| ProgramError::InvalidAccountData)?
+        };
+        if token_metadata.mint != ctx.accounts.mint.key() {
+            return Err(ProgramError::InvalidAccountData.into());
+        }
+        check_update_authority(
+            &token_metadata,
+            ctx.accounts.update_authority.key,
+            ctx.accounts.instructions.as_deref(),
+        )?;
+
+        // Restore the length, then size the account and its rent to the value
+        let len = u32::try_from(metadata_packed_len(&token_metadata)?)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        metadata.try_borrow_mut_data()?[8..12].copy_from_slice(&len.to_le_bytes());
+        msg!("Repaired TLV length: {}", len);
+
+        realloc_metadata(
+            metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program.to_account_info(),
+            &token_metadata,
+            false,
+        )
+    }
+}
+
+// Order of the accounts in the struct matters
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    /// CHECK: Create this account in instruction
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()], 
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Optional update authority, unchecked because it can either be SystemAccount or a PDA owned by another program
+    pub update_authority: Option<UncheckedAccount<'info>>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub mint_authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Program config, approvals are not required if it was never initialized
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Approval for the mint, only checked if required by the config
+    #[account(seeds = [b"approved", mint.key().as_ref()], bump)]
+    pub approval: UncheckedAccount<'info>,
+    /// Registry the mint is appended to, if passed
+    #[account(mut, seeds = [b"registry"], bump)]
+    pub registry: Option<Account<'info, Registry>>,
+    /// CHECK: Current registry page, checked and created on first use in instruction
+    #[account(mut)]
+    pub registry_page: Option<UncheckedAccount<'info>>,
+    /// CHECK: Claim on the symbol, checked and created in instruction, if passed
+    #[account(mut)]
+    pub symbol_claim: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct InitializeData {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateField<'info> {
+    /// CHECK: check by address only, no anchor type to check against,
+    /// `bump` without a value only accepts the canonical bump, also on the raw interface
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// Change log the update is recorded in, if passed
+    #[account(mut, seeds = [b"change_log", mint.key().as_ref()], bump)]
+    pub change_log: Option<Account<'info, ChangeLog>>,
+    /// CHECK: Receives the update fee, required and checked in instruction when one is set
+    #[account(mut)]
+    pub fee_destination: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpdateFieldData {
+    /// Field to update in the metadata
+    pub field: AnchorField,
+    /// Value to write for the field
+    pub value: String,
+}
+
+/// Emitted through a self-CPI by instructions using the `Initialize` accounts,
+/// with the `event-cpi` feature
+#[event]
+pub struct MetadataInitialized {
+    pub mint: Pubkey,
+    pub update_authority: Option<Pubkey>,
+}
+
+/// Emitted through a self-CPI by `update_field`, with the `event-cpi` feature
+#[event]
+pub struct FieldUpdated {
+    pub mint: Pubkey,
+    pub field: AnchorField,
+    pub value: String,
+}
+
+#[derive(Accounts)]
+pub struct CloseMetadata<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Claim on the stored symbol, checked in instruction and closed if held by the mint
+    #[account(mut)]
+    pub symbol_claim: UncheckedAccount<'info>,
+    /// CHECK: Receives the rent of the closed accounts
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RelayUpdateField<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// Relayer submitting the update signed by the update authority
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, holding the Ed25519 signature verification
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    /// CHECK: Receives the update fee, required and checked in instruction when one is set
+    #[account(mut)]
+    pub fee_destination: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyAttestation<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// Submits the attestation signed by the attester
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, holding the Ed25519 signature verification
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSelf<'info> {
+    /// CHECK: Create this account in instruction
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Program config, approvals are not required if it was never initialized
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Approval for the mint, only checked if required by the config
+    #[account(seeds = [b"approved", mint.key().as_ref()], bump)]
+    pub approval: UncheckedAccount<'info>,
+    /// Registry the mint is appended to, if passed
+    #[account(mut, seeds = [b"registry"], bump)]
+    pub registry: Option<Account<'info, Registry>>,
+    /// CHECK: Current registry page, checked and created on first use in instruction
+    #[account(mut)]
+    pub registry_page: Option<UncheckedAccount<'info>>,
+    /// CHECK: Claim on the symbol, checked and created in instruction, if passed
+    #[account(mut)]
+    pub symbol_claim: Option<UncheckedAccount<'info>>,
+}
+
+/// Mint, metadata and approval accounts of each item are passed as remaining accounts
+#[derive(Accounts)]
+pub struct InitializeBatch<'info> {
+    /// Mint authority of every mint, and update authority of every metadata account
+    pub mint_authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Program config, approvals are not required if it was never initialized
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// Registry every mint is appended to, if passed
+    #[account(mut, seeds = [b"registry"], bump)]
+    pub registry: Option<Account<'info, Registry>>,
+    /// CHECK: Current registry page, checked and created on first use in instruction,
+    /// a batch crossing into the next page fails and has to be split there
+    #[account(mut)]
+    pub registry_page: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(decimals: u8)]
+pub struct CreateMintAndMetadata<'info> {
+    /// CHECK: Create this account in instruction
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Optional update authority, unchecked because it can either be SystemAccount or a PDA owned by another program
+    pub update_authority: Option<UncheckedAccount<'info>>,
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = decimals,
+        mint::authority = mint_authority,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Mint authority of the new mint, unchecked because it can either be SystemAccount or a PDA owned by another program
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Program config, approvals are not required if it was never initialized
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Approval for the mint, only checked if required by the config
+    #[account(seeds = [b"approved", mint.key().as_ref()], bump)]
+    pub approval: UncheckedAccount<'info>,
+    /// Registry the mint is appended to, if passed
+    #[account(mut, seeds = [b"registry"], bump)]
+    pub registry: Option<Account<'info, Registry>>,
+    /// CHECK: Current registry page, checked and created on first use in instruction
+    #[account(mut)]
+    pub registry_page: Option<UncheckedAccount<'info>>,
+    /// CHECK: Claim on the symbol, checked and created in instruction, if passed
+    #[account(mut)]
+    pub symbol_claim: Option<UncheckedAccount<'info>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(label: String)]
+pub struct InitializeLabeled<'info> {
+    /// CHECK: Create this account in instruction
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref(), label.as_bytes()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Optional update authority, unchecked because it can either be SystemAccount or a PDA owned by another program
+    pub update_authority: Option<UncheckedAccount<'info>>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub mint_authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Program config, approvals are not required if it was never initialized
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Approval for the mint, only checked if required by the config
+    #[account(seeds = [b"approved", mint.key().as_ref()], bump)]
+    pub approval: UncheckedAccount<'info>,
+    /// Registry the mint is appended to, if passed
+    #[account(mut, seeds = [b"registry"], bump)]
+    pub registry: Option<Account<'info, Registry>>,
+    /// CHECK: Current registry page, checked and created on first use in instruction
+    #[account(mut)]
+    pub registry_page: Option<UncheckedAccount<'info>>,
+    /// CHECK: Claim on the symbol, checked and created in instruction, if passed
+    #[account(mut)]
+    pub symbol_claim: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(label: String)]
+pub struct UpdateFieldLabeled<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref(), label.as_bytes()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// CHECK: Receives the update fee, required and checked in instruction when one is set
+    #[account(mut)]
+    pub fee_destination: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePda<'info> {
+    /// CHECK: Metadata account at the legacy address, data deserialized in instruction
+    #[account(
+        mut,
+        owner = id(),
+        seeds = [LEGACY_METADATA_SEED, mint.key().as_ref()],
+        bump)
+    ]
+    pub old_metadata: UncheckedAccount<'info>,
+    /// CHECK: Create this account in instruction
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFieldNoMint<'info> {
+    /// CHECK: PDA checked against the mint stored in the metadata in instruction
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// CHECK: Receives the update fee, required and checked in instruction when one is set
+    #[account(mut)]
+    pub fee_destination: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct OracleSetField<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub oracle: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump)
+    ]
+    pub config: Account<'info, Config>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::TokenMetadata>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key()))]
+    pub program_data: Account<'info, ProgramData>,
+    pub upgrade_authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = admin)
+    ]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMint<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = admin)
+    ]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MintApproval::INIT_SPACE,
+        seeds = [b"approved", mint.key().as_ref()],
+        bump)
+    ]
+    pub approval: Account<'info, MintApproval>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeMintApproval<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = admin)
+    ]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"approved", mint.key().as_ref()],
+        bump)
+    ]
+    pub approval: Account<'info, MintApproval>,
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Registry::INIT_SPACE,
+        seeds = [b"registry"],
+        bump)
+    ]
+    pub registry: Account<'info, Registry>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeChangeLog<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ChangeLog::INIT_SPACE,
+        seeds = [b"change_log", mint.key().as_ref()],
+        bump)
+    ]
+    pub change_log: Account<'info, ChangeLog>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u32)]
+pub struct ReadRegistryPage<'info> {
+    #[account(
+        seeds = [b"registry", &page.to_le_bytes()],
+        bump)
+    ]
+    pub registry_page: Account<'info, RegistryPage>,
+}
+
+#[derive(Accounts)]
+pub struct CreateGovernance<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Governance::INIT_SPACE,
+        seeds = [b"governance", mint.key().as_ref()],
+        bump)
+    ]
+    pub governance: Account<'info, Governance>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64, data: UpdateFieldData)]
+pub struct ProposeUpdate<'info> {
+    #[account(
+        seeds = [b"governance", mint.key().as_ref()],
+        bump)
+    ]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        init,
+        payer = proposer,
+        space = VersionedProposal::space(&data),
+        seeds = [b"proposal", mint.key().as_ref(), &nonce.to_le_bytes()],
+        bump)
+    ]
+    pub proposal: Account<'info, VersionedProposal>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProposal<'info> {
+    /// CHECK: Proposal in the unversioned layout, owner checked and data deserialized in instruction
+    #[account(mut, owner = id())]
+    pub proposal: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteUpdate<'info> {
+    #[account(
+        seeds = [b"governance", mint.key().as_ref()],
+        bump)
+    ]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        mut,
+        seeds = [b"proposal", mint.key().as_ref(), &proposal.nonce.to_le_bytes()],
+        bump)
+    ]
+    pub proposal: Account<'info, VersionedProposal>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteUpdate<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"governance", mint.key().as_ref()],
+        bump)
+    ]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"proposal", mint.key().as_ref(), &proposal.nonce.to_le_bytes()],
+        bump)
+    ]
+    pub proposal: Account<'info, VersionedProposal>,
+    /// CHECK: Receives the rent of the closed proposal, checked against the proposal
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// CHECK: Receives the update fee, required and checked in instruction when one is set
+    #[account(mut)]
+    pub fee_destination: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct MoveKey<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", source_mint.key().as_ref()],
+        bump)
+    ]
+    pub source_metadata: UncheckedAccount<'info>,
+    pub source_authority: Signer<'info>,
+    pub source_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", destination_mint.key().as_ref()],
+        bump)
+    ]
+    pub destination_metadata: UncheckedAccount<'info>,
+    pub destination_authority: Signer<'info>,
+    pub destination_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RebalanceRent<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", source_mint.key().as_ref()],
+        bump)
+    ]
+    pub source_metadata: UncheckedAccount<'info>,
+    pub source_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", destination_mint.key().as_ref()],
+        bump)
+    ]
+    pub destination_metadata: UncheckedAccount<'info>,
+    pub destination_mint: InterfaceAccount<'info, Mint>,
+    /// Update authority of both metadata accounts
+    pub update_authority: Signer<'info>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAuthority<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub claimant: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAuthority<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    /// Update authority, or the admin if one is set
+    pub authority: Signer<'info>,
+    /// CHECK: New update authority, only read for its balance if the policy requires it
+    pub new_authority: Option<UncheckedAccount<'info>>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RepairSize<'info> {
+    /// CHECK: TLV length may be corrupt, decoded without it in instruction
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub update_authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Instructions sysvar, required when the metadata has an authority program
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ReadMetadata<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+#[instruction(label: String)]
+pub struct ReadMetadataLabeled<'info> {
+    /// CHECK: check by address only, no anchor type to check against
+    #[account(
+        seeds = [b"metadata", mint.key().as_ref(), label.as_bytes()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+/// TokenMetadata as returned by `get_metadata`, borsh encoded in the return data.
+/// Listed in the IDL so clients decode it without unpacking the TLV account
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetadataView {
+    /// Default pubkey if the metadata is immutable
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    /// Sorted by key
+    pub additional_metadata: Vec<MetadataEntry>,
+}
+
+/// Metadata with the live supply of its mint, as returned by `get_metadata_with_supply`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetadataWithSupply {
+    pub metadata: MetadataView,
+    pub supply: u64,
+    pub decimals: u8,
+}
+
+/// Metadata with a page of its custom keys, as returned by `get_metadata_page`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetadataPage {
+    pub metadata: MetadataView,
+    /// Number of custom keys across all pages
+    pub total: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetadataEntry {
+    pub key: String,
+    pub value: String,
+}
+
+impl From<TokenMetadata> for MetadataView {
+    fn from(token_metadata: TokenMetadata) -> Self {
+        // Sorted by key so reads compare equal regardless of storage order
+        let mut additional_metadata: Vec<MetadataEntry> = token_metadata
+            .additional_metadata
+            .into_iter()
+            .map(|(key, value)| MetadataEntry { key, value })
+            .collect();
+        additional_metadata.sort_by(|a, b| a.key.cmp(&b.key));
+
+        MetadataView {
+            update_authority: Option::<Pubkey>::from(token_metadata.update_authority)
+                .unwrap_or_default(),
+            mint: token_metadata.mint,
+            name: token_metadata.name,
+            symbol: token_metadata.symbol,
+            uri: token_metadata.uri,
+            additional_metadata,
+        }
+    }
+}
+
+/// Display hints for rendering token amounts
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct DisplayConfig {
+    /// Number of decimals to show, at most the decimals of the mint
+    pub decimals_override: Option<u8>,
+    /// Text shown before the amount
+    pub prefix: String,
+    /// Text shown after the amount
+    pub suffix: String,
+}
+
+/// Primary and fallback URI, as returned by `get_uris`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct Uris {
+    pub uri: String,
+    /// Tried by clients when the primary URI is unreachable
+    pub fallback_uri: Option<String>,
+}
+
+/// Name and symbol for a locale, as returned by `get_localized`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LocalizedNames {
+    pub name: String,
+    pub symbol: String,
+}
+
+/// Rent status of a metadata account
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RentInfo {
+    /// Current size of the account in bytes
+    pub size: u64,
+    /// Rent-exempt minimum balance for the current size
+    pub rent_exempt_minimum: u64,
+    /// Current balance, above the minimum if a shrink refund is held in the account
+    pub lamports: u64,
+}
+
+/// Attestation status, as returned by `get_attestation`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct Attestation {
+    /// Hash of the current content, signed by the attester as part of the attestation message
+    pub hash: [u8; 32],
+    /// Whether the attestation covers the current content
+    pub attested: bool,
+    /// Attester of the last verified attestation
+    pub attester: Option<Pubkey>,
+}
+
+/// Sizes of a metadata account, as returned by `get_size`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetadataSize {
+    /// Length of the account data
+    pub data_len: u64,
+    /// Length of the packed TokenMetadata, as stored in the TLV entry
+    pub value_len: u32,
+}
+
+/// Metadata accounts are passed as remaining accounts, after this program
+#[derive(Accounts)]
+pub struct ReadBatch<'info> {
+    /// This program. Anchor 0.29 generates the CPI client of an empty struct without the
+    /// `'info` lifetime its CPI functions pass it, which fails to compile with the `cpi`
+    /// feature, so the struct needs one account. This program is the one every caller has
+    pub token_metadata_program: Program<'info, program::TokenMetadata>,
+}
+
+#[derive(Accounts)]
+pub struct DiffMetadata<'info> {
+    /// CHECK: checked to be initialized metadata in instruction
+    pub metadata_a: UncheckedAccount<'info>,
+    /// CHECK: checked to be initialized metadata in instruction
+    pub metadata_b: UncheckedAccount<'info>,
+}
+
+/// Fields that differ between two metadata accounts, as returned by `diff_metadata`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetadataDiff {
+    pub name: bool,
+    pub symbol: bool,
+    pub uri: bool,
+    /// Custom keys missing from either account or with different values, sorted
+    pub keys: Vec<String>,
+    /// Whether keys were left out to fit the return data limit
+    pub truncated: bool,
+}
+
+// Need to do this so the enum shows up in the IDL
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum AnchorField {
+    /// The name field, corresponding to `TokenMetadata.name`
+    Name,
+    /// The symbol field, corresponding to `TokenMetadata.symbol`
+    Symbol,
+    /// The uri field, corresponding to `TokenMetadata.uri`
+    Uri,
+    /// A user field, whose key is given by the associated string
+    Key(String),
+}
+
+// Convert AnchorField to Field from spl_token_metadata_interface
+impl AnchorField {
+    fn to_field(&self) -> Field {
+        match self {
+            AnchorField::Name => Field::Name,
+            AnchorField::Symbol => Field::Symbol,
+            AnchorField::Uri => Field::Uri,
+            AnchorField::Key(s) => Field::Key(s.clone()),
+        }
+    }
+
+    // Identifies the field in the suffix of reserved keys about it
+    fn key_suffix(&self) -> String {
+        match self {
+            AnchorField::Name => "name".to_string(),
+            AnchorField::Symbol => "symbol".to_string(),
+            AnchorField::Uri => "uri".to_string(),
+            AnchorField::Key(key) => format!("key.{key}"),
+        }
+    }
+}
+
+/// Boolean attributes set by the update authority, stored together as bits of `FLAGS_KEY`.
+/// The bit of a flag is its position in the enum, so new flags are only ever appended.
+/// Flags are informational, `Revealed` is independent of `reveal` for instance
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum MetadataFlag {
+    /// Bit 0
+    Verified,
+    /// Bit 1
+    Frozen,
+    /// Bit 2
+    Revealed,
+    /// Bit 3
+    Attested,
+}
+
+impl MetadataFlag {
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+// Reserved key storing the placeholder of a hidden field
+fn hidden_key(field: &AnchorField) -> String {
+    format!("{HIDDEN_KEY_PREFIX}{}", field.key_suffix())
+}
+
+// Reserved key storing the commitment to the field's value
+fn commit_key(field: &AnchorField) -> String {
+    format!("{COMMIT_KEY_PREFIX}{}", field.key_suffix())
+}
+
+// Accounts every initialize path hands to `initialize_metadata`
+struct InitializeAccounts<'a, 'info> {
+    metadata: &'a AccountInfo<'info>,
+    // Seeds of the metadata account, including the bump
+    metadata_seeds: &'a [&'a [u8]],
+    update_authority: Option<&'a AccountInfo<'info>>,
+    mint: &'a Mint,
+    mint_key: Pubkey,
+    mint_authority: &'a Pubkey,
+    payer: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+    config: &'a AccountInfo<'info>,
+    approval: &'a AccountInfo<'info>,
+    registry: Option<&'a mut Registry>,
+    registry_page: Option<&'a AccountInfo<'info>>,
+    symbol_claim: Option<&'a AccountInfo<'info>>,
+}
+
+// Initialize through the `Initialize` accounts, emitting the event with the `event-cpi` feature
+fn process_initialize(
+    ctx: Context<Initialize>,
+    build: impl FnOnce(Option<Pubkey>, Pubkey) -> Result<TokenMetadata>,
+) -> Result<()> {
+    let mint = ctx.accounts.mint.key();
+    let bump = [ctx.bumps.metadata];
+    #[cfg_attr(not(feature = "event-cpi"), allow(unused_variables))]
+    let token_metadata = initialize_metadata(
+        InitializeAccounts {
+            metadata: &ctx.accounts.metadata,
+            metadata_seeds: &[b"metadata", mint.as_ref(), &bump],
+            update_authority: ctx.accounts.update_authority.as_deref(),
+            mint: &ctx.accounts.mint,
+            mint_key: mint,
+            mint_authority: ctx.accounts.mint_authority.key,
+            payer: &ctx.accounts.payer,
+            system_program: &ctx.accounts.system_program,
+            config: &ctx.accounts.config,
+            approval: &ctx.accounts.approval,
+            registry: ctx.accounts.registry.as_deref_mut(),
+            registry_page: ctx.accounts.registry_page.as_deref(),
+            symbol_claim: ctx.accounts.symbol_claim.as_deref(),
+        },
+        build,
+    )?;
+
+    #[cfg(feature = "event-cpi")]
+    emit_cpi!(MetadataInitialized {
+        mint,
+        update_authority: Option::<Pubkey>::from(token_metadata.update_authority),
+    });
+    Ok(())
+}
+
+// Check the mint, then create the metadata account built by `build` from the update authority
+// and mint, registering the mint and claiming the symbol if those accounts were passed
+fn initialize_metadata(
+    accounts: InitializeAccounts,
+    build: impl FnOnce(Option<Pubkey>, Pubkey) -> Result<TokenMetadata>,
+) -> Result<TokenMetadata> {
+    // Check mint authority
+    check_mint_authority(accounts.mint, accounts.mint_authority)?;
+
+    // Check mint is approved, if required
+    check_mint_approval(accounts.config, accounts.approval, &accounts.mint_key)?;
+
+    // Construct token metadata
+    let update_authority = accounts.update_authority.map(|account| *account.key);
+    let mut token_metadata = build(update_authority, accounts.mint_key)?;
+    if accounts.symbol_claim.is_some() {
+        token_metadata.update(
+            Field::Key(SYMBOL_CLAIM_KEY.to_string()),
+            token_metadata.symbol.clone(),
+        );
+        // Again, so the integrity hash covers the claim
+        finalize_metadata(&mut token_metadata)?;
+    }
+    check_freeze_authority(&token_metadata, accounts.mint)?;
+    if let Some(update_authority) = accounts.update_authority {
+        check_authority_funded(&token_metadata, update_authority)?;
+    }
+
+    // Create and initialize metadata account
+    create_metadata_account(
+        accounts.metadata,
+        accounts.payer,
+        accounts.system_program,
+        &[accounts.metadata_seeds],
+        &token_metadata,
+    )?;
+
+    // Register the mint, if the registry was passed
+    if let Some(registry) = accounts.registry {
+        let registry_page = accounts
+            .registry_page
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        append_to_registry(
+            registry,
+            registry_page,
+            accounts.payer,
+            accounts.system_program,
+            accounts.mint_key,
+        )?;
+    }
+
+    // Claim the symbol, if the symbol claim was passed
+    if let Some(symbol_claim) = accounts.symbol_claim {
+        claim_symbol(
+            symbol_claim,
+            accounts.payer,
+            accounts.system_program,
+            &token_metadata.symbol,
+            accounts.mint_key,
+        )?;
+    }
+    Ok(token_metadata)
+}
+
+// Construct the TokenMetadata for a new metadata account
+fn new_token_metadata(
+    update_authority_key: Option<Pubkey>,
+    mint: Pubkey,
+    data: InitializeData,
+    policy: &Policy,
+) -> Result<TokenMetadata> {
+    // Empty fields only cost their 4 byte length prefix, which the interface layout requires,
+    // but metadata with no core fields at all is almost certainly a mistake
+    // unless reserved on purpose through `initialize_empty`
+    if data.name.is_empty() && data.symbol.is_empty() && data.uri.is_empty() {
+        return err!(MetadataError::EmptyMetadata);
+    }
+    build_token_metadata(update_authority_key, mint, data, policy)
+}
+
+// Construct the TokenMetadata for a new metadata account, allowing empty core fields
+fn build_token_metadata(
+    update_authority_key: Option<Pubkey>,
+    mint: Pubkey,
+    data: InitializeData,
+    policy: &Policy,
+) -> Result<TokenMetadata> {
+    // Reject instead of silently making the metadata immutable
+    if update_authority_key == Some(Pubkey::default()) {
+        return err!(MetadataError::InvalidUpdateAuthority);
+    }
+    let update_authority = OptionalNonZeroPubkey::try_from(update_authority_key)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+
+    let mut token_metadata = TokenMetadata {
+        name: data.name,
+        symbol: data.symbol,
+        uri: data.uri,
+        update_authority,
+        mint,
+        ..Default::default()
+    };
+    write_policy(&mut token_metadata, policy);
+    finalize_metadata(&mut token_metadata)?;
+    msg!("TokenMetadata: {:?}", token_metadata);
+    Ok(token_metadata)
+}
+
+// Parse items encoded as u16 length prefixed name, symbol and uri, the layout of `emit_compact`
+fn parse_compact_items(payload: &[u8]) -> Result<Vec<InitializeData>> {
+    let mut items = Vec::new();
+    let mut rest = payload;
+    while !rest.is_empty() {
+        items.push(InitializeData {
+            name: take_compact_str(&mut rest)?,
+            symbol: take_compact_str(&mut rest)?,
+            uri: take_compact_str(&mut rest)?,
+        });
+    }
+    Ok(items)
+}
+
+// Split one u16 length prefixed string off the front of the payload
+fn take_compact_str(rest: &mut &[u8]) -> Result<String> {
+    let input = *rest;
+    let len = input
+        .get(..2)
+        .map(|len| u16::from_le_bytes([len[0], len[1]]) as usize)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let bytes = input
+        .get(2..2 + len)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let value = std::str::from_utf8(bytes).map_err(|_| ProgramError::InvalidInstructionData)?;
+    *rest = &input[2 + len..];
+    Ok(value.to_string())
+}
+
+// Create the metadata account of each item, with the mint authority as update authority
+fn process_initialize_batch<'info>(
+    accounts: &mut InitializeBatch<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    items: Vec<InitializeData>,
+) -> Result<()> {
+    require!(
+        items.len() <= MAX_BATCH_INITIALIZE,
+        MetadataError::TooManyAccounts
+    );
+    if remaining_accounts.len() != items.len() * 3 {
+        return Err(ProgramError::NotEnoughAccountKeys.into());
+    }
+
+    for (data, item_accounts) in items.into_iter().zip(remaining_accounts.chunks(3)) {
+        let [mint, metadata, approval] = item_accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys.into());
+        };
+
+        // Checks the accounts struct of `initialize` would otherwise perform
+        if mint.owner != &anchor_spl::token::ID && mint.owner != &anchor_spl::token_2022::ID {
+            return Err(ProgramError::IllegalOwner.into());
+        }
+        let mint_state = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?;
+
+        let (metadata_key, bump) =
+            Pubkey::find_program_address(&[b"metadata", mint.key.as_ref()], &id());
+        if *metadata.key != metadata_key {
+            return Err(ProgramError::InvalidSeeds.into());
+        }
+
+        // Approvals are only created by this program, so its ownership stands in for the seeds
+        if !approval.data_is_empty() && approval.owner != &id() {
+            return Err(ProgramError::IllegalOwner.into());
+        }
+
+        let bump = [bump];
+        initialize_metadata(
+            InitializeAccounts {
+                metadata,
+                metadata_seeds: &[b"metadata", mint.key.as_ref(), &bump],
+                update_authority: Some(&accounts.mint_authority),
+                mint: &mint_state,
+                mint_key: *mint.key,
+                mint_authority: accounts.mint_authority.key,
+                payer: &accounts.payer,
+                system_program: &accounts.system_program,
+                config: &accounts.config,
+                approval,
+                registry: accounts.registry.as_deref_mut(),
+                registry_page: accounts.registry_page.as_deref(),
+                symbol_claim: None,
+            },
+            |update_authority, mint| {
+                new_token_metadata(update_authority, mint, data, &Policy::default())
+            },
+        )?;
+    }
+    Ok(())
+}
+
+// Create the metadata account and pack the TokenMetadata into it
+fn create_metadata_account<'info>(
+    metadata: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    token_metadata: &TokenMetadata,
+) -> Result<()> {
+    // Calculate size and lamports for the metadata account
+    let size = metadata_tlv_size(token_metadata)?;
+    let lamports = rent_exempt_lamports(size)?;
+
+    // Create metadata account
+    // No recovery path is needed for a failure after this point, as the runtime
+    // rolls back the created account along with the rest of a failed transaction
+    create_account(
+        CpiContext::new(
+            system_program.clone(),
+            CreateAccount {
+                from: payer.clone(),
+                to: metadata.clone(),
+            },
+        )
+        .with_signer(signer_seeds),
+        lamports,
+        size as u64,
+        &id(),
+    )?;
+
+    // Initialize metadata account data
+    let instance_size = metadata_packed_len(token_metadata)?;
+    let mut buffer = metadata.try_borrow_mut_data()?;
+    let data_len = buffer.len();
+    let mut state = TlvStateMut::unpack(&mut buffer)?;
+    state.alloc::<TokenMetadata>(instance_size, false)?;
+    state.pack_first_variable_len_value(token_metadata)?;
+
+    // Guard against tlv_size_of and get_instance_packed_len drifting apart
+    let packed_len = state.get_first_bytes::<TokenMetadata>()?.len();
+    if packed_len != instance_size || data_len != size {
+        msg!(
+            "Packed {} of {} bytes into account of {} bytes, expected {}",
+            packed_len,
+            instance_size,
+            data_len,
+            size
+        );
+        return err!(MetadataError::SizeMismatch);
+    }
+    Ok(())
+}
+
+// Apply an UpdateField to the metadata account
+#[allow(clippy::too_many_arguments)]
+fn process_update_field<'info>(
+    metadata: &AccountInfo<'info>,
+    mint: Option<&InterfaceAccount<'info, Mint>>,
+    update_authority: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    instructions: Option<&AccountInfo<'info>>,
+    change_log: Option<&mut ChangeLog>,
+    fee_destination: Option<&AccountInfo<'info>>,
+    data: UpdateFieldData,
+) -> Result<()> {
+    // The lamport reconciliation below assumes two distinct accounts.
+    require_keys_neq!(payer.key(), metadata.key(), MetadataError::InvalidPayer);
+
+    // Get current TokenMetadata.
+    let mut token_metadata = load_token_metadata(metadata)?;
+
+    // Check update authority, or the delegate, and the authority program.
+    let is_delegate =
+        check_update_authority_or_delegate(&token_metadata, update_authority, instructions)?;
+    // Refunds from a delegate's shrink stay in the account until the authority claims them.
+    let hold_refund =
+        is_delegate && find_key_value(&token_metadata, DELEGATE_HOLD_REFUNDS_KEY).is_some();
+
+    // Reserved keys are managed by the program, core fields are set through their own variants.
+    if let AnchorField::Key(key) = &data.field {
+        check_key_not_reserved(key)?;
+        check_key_not_core_field(key)?;
+    }
+
+    // Updates wait for the mint supply to reach the threshold, if one is set,
+    // and freezable mints are rejected, if the policy requires it.
+    // Both read the mint, so updates without it are rejected under these policies.
+    if let Some(mint) = mint {
+        check_supply_threshold(&token_metadata, mint.supply)?;
+        check_freeze_authority(&token_metadata, mint)?;
+    } else if find_key_value(&token_metadata, SUPPLY_THRESHOLD_KEY).is_some()
+        || find_key_value(&token_metadata, REJECT_FREEZABLE_KEY).is_some()
+    {
+        return Err(ProgramError::NotEnoughAccountKeys.into());
+    }
+
+    // Nothing changes after the lock time, if one is set.
+    check_time_lock(&token_metadata)?;
+
+    // Locked fields can't be updated.
+    check_field_unlocked(&token_metadata, &data.field)?;
+
+    // Bound the value before packing.
+    require!(
+        data.value.len() <= MAX_VALUE_LEN,
+        MetadataError::ValueTooLong
+    );
+
+    // Setting a field to its current value leaves the account as is.
+    let current = match &data.field {
+        AnchorField::Name => Some(token_metadata.name.as_str()),
+        AnchorField::Symbol => Some(token_metadata.symbol.as_str()),
+        AnchorField::Uri => Some(token_metadata.uri.as_str()),
+        AnchorField::Key(key) => find_key_value(&token_metadata, key),
+    };
+    if current == Some(data.value.as_str()) {
+        msg!("Field already set, skipping update");
+        return Ok(());
+    }
+
+    // Charge the update fee, if one is set.
+    charge_update_fee(&token_metadata, payer, fee_destination, system_program)?;
+
+    // Record the update for consumers of the change log, if passed.
+    if let Some(change_log) = change_log {
+        let field_key = match &data.field {
+            AnchorField::Name => "name",
+            AnchorField::Symbol => "symbol",
+            AnchorField::Uri => "uri",
+            AnchorField::Key(key) => key,
+        };
+        change_log.record(ChangeRecord {
+            slot: Clock::get()?.slot,
+            field_hash: hash(field_key.as_bytes()).to_bytes(),
+            value_hash: hash(data.value.as_bytes()).to_bytes(),
+        });
+    }
+
+    // Record who made the update, for audit trails.
+    token_metadata.update(
+        Field::Key(LAST_UPDATER_KEY.to_string()),
+        update_authority.to_string(),
+    );
+
+    // Perform the update on the TokenMetadata.
+    let field = data.field.to_field();
+    token_metadata.update(field, data.value);
+    finalize_metadata(&mut token_metadata)?;
+    msg!("TokenMetadata: {:?}", token_metadata);
+
+    realloc_metadata(
+        metadata,
+        payer,
+        system_program,
+        &token_metadata,
+        hold_refund,
+    )
+}
+
+// Transfer the update fee from the payer to the stored destination, if a fee is set
+fn charge_update_fee<'info>(
+    token_metadata: &TokenMetadata,
+    payer: &AccountInfo<'info>,
+    fee_destination: Option<&AccountInfo<'info>>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let fee = find_key_value(token_metadata, UPDATE_FEE_KEY)
+        .map(u64::from_str)
+        .transpose()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let Some(fee) = fee else {
+        return Ok(());
+    };
+    let destination = fee_destination
+        .filter(|account| {
+            find_key_value(token_metadata, FEE_DESTINATION_KEY)
+                == Some(account.key.to_string().as_str())
+        })
+        .ok_or(MetadataError::IncorrectFeeDestination)?;
+
+    msg!("Charging update fee of {} lamports", fee);
+    transfer(
+        CpiContext::new(
+            system_program.clone(),
+            Transfer {
+                from: payer.clone(),
+                to: destination.clone(),
+            },
+        ),
+        fee,
+    )
+}
+
+// Resize the metadata account to fit the TokenMetadata, settling the rent with the payer,
+// unless `hold_refund` keeps excess lamports in the account
+fn realloc_metadata<'info>(
+    metadata: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_metadata: &TokenMetadata,
+    hold_refund: bool,
+) -> Result<()> {
+    // Calculate the required size and lamports for the updated metadata.
+    let new_size = metadata_tlv_size(token_metadata)?;
+    let required_lamports = rent_exempt_lamports(new_size)?;
+
+    // Get current state of the metadata account.
+    let current_lamports = metadata.lamports();
+
+    // Transfer lamports if required.
+    if required_lamports != current_lamports {
+        let lamport_difference = required_lamports.abs_diff(current_lamports);
+        if required_lamports > current_lamports {
+            // Checked first, the failed transfer would only surface a system program error.
+            require!(
+                payer.lamports() >= lamport_difference,
+                MetadataError::PayerInsufficientFunds
+            );
+            // Transfer additional lamports to metadata account.
+            msg!(
+                "Transferring {} lamports to metadata account",
+                lamport_difference
+            );
+            transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    Transfer {
+                        from: payer.clone(),
+                        to: metadata.clone(),
+                    },
+                ),
+                lamport_difference,
+            )?;
+        } else if hold_refund {
+            msg!(
+                "Holding {} lamports in metadata account",
+                lamport_difference
+            );
+        } else {
+            // Transfer excess lamports back to payer.
+            msg!("Transferring {} lamports back to payer", lamport_difference);
+            // Modify lamports directly because metadata account is owned by this program (and not System Program)
+            metadata.sub_lamports(lamport_difference)?;
+            payer.add_lamports(lamport_difference)?;
+        }
+    }
+
+    // Reallocate and update the metadata account data.
+    // Same size is common for fixed-format values, the entry is repacked without a realloc
+    if new_size == metadata.data_len() {
+        msg!("Packing metadata in place");
+        let mut buffer = metadata.try_borrow_mut_data()?;
+        let mut state = TlvStateMut::unpack(&mut buffer)?;
+        state.pack_first_variable_len_value(token_metadata)?;
+    } else {
+        realloc_and_pack_first_variable_len(metadata, token_metadata)?;
+    }
+    Ok(())
+}
+
+// Rent-exempt minimum for a metadata account, rejecting sizes past `MAX_METADATA_SIZE`
+fn rent_exempt_lamports(size: usize) -> Result<u64> {
+    require!(size <= MAX_METADATA_SIZE, MetadataError::MetadataTooLarge);
+    Ok(Rent::get()?.minimum_balance(size))
+}
+
+// Labels are used as a PDA seed, an empty label would collide with the unlabeled PDA
+fn check_label(label: &str) -> Result<()> {
+    if label.is_empty() || label.len() > MAX_SEED_LEN {
+        return err!(MetadataError::InvalidLabel);
+    }
+    Ok(())
+}
+
+// Return the values of the keys, each prefixed with its u16 length in request order,
+// a missing key is only the sentinel
+fn return_key_values(token_metadata: &TokenMetadata, keys: &[String]) -> Result<()> {
+    require!(keys.len() <= MAX_GET_KEYS, MetadataError::TooManyKeys);
+    let mut values = Vec::new();
+    for key in keys {
+        match find_key_value(token_metadata, key) {
+            Some(value) => {
+                let len =
+                    u16::try_from(value.len()).map_err(|_| ProgramError::InvalidAccountData)?;
+                values.extend_from_slice(&len.to_le_bytes());
+                values.extend_from_slice(value.as_bytes());
+            }
+            None => values.extend_from_slice(&MISSING_KEY_SENTINEL.to_le_bytes()),
+        }
+    }
+    require!(
+        values.len() <= MAX_RETURN_DATA,
+        MetadataError::RangeOutOfBounds
+    );
+    set_return_data(&values);
+    Ok(())
+}
+
+// Packed length of the TokenMetadata value
+fn metadata_packed_len<T: AnchorSerialize>(token_metadata: &T) -> Result<usize> {
+    get_instance_packed_len(token_metadata)
+        .map_err(|_| error!(MetadataError::MetadataSerializationFailed))
+}
+
+// Size of the metadata account required to hold the TokenMetadata
+fn metadata_tlv_size(token_metadata: &TokenMetadata) -> Result<usize> {
+    TokenMetadata::tlv_size_of(token_metadata)
+        .map_err(|_| error!(MetadataError::MetadataSerializationFailed))
+}
+
+// The update authority and mint are the first fields of the packed TokenMetadata,
+// so read them directly at their offset instead of deserializing the whole value
+fn read_packed_key(metadata: &AccountInfo, offset: usize) -> Result<[u8; 32]> {
+    check_metadata_initialized(metadata)?;
+    let buffer = metadata.try_borrow_data()?;
+    let state = TlvStateBorrowed::unpack(&buffer)?;
+    let bytes = state.get_first_bytes::<TokenMetadata>()?;
+    bytes
+        .get(offset..offset + 32)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| ProgramError::InvalidAccountData.into())
+}
+
+// Deserialize the TokenMetadata as returned by reads, with the placeholders of hidden fields
+// until revealed. The stored values are still readable from the account data
+fn load_masked_metadata(metadata: &AccountInfo) -> Result<TokenMetadata> {
+    let mut token_metadata = load_token_metadata(metadata)?;
+    if find_key_value(&token_metadata, REVEALED_KEY).is_some() {
+        return Ok(token_metadata);
+    }
+
+    let hidden: Vec<(String, String)> = token_metadata
+        .additional_metadata
+        .iter()
+        .filter_map(|(key, placeholder)| {
+            key.strip_prefix(HIDDEN_KEY_PREFIX)
+                .map(|suffix| (suffix.to_string(), placeholder.clone()))
+        })
+        .collect();
+    for (suffix, placeholder) in hidden {
+        match suffix.as_str() {
+            "name" => token_metadata.name = placeholder,
+            "symbol" => token_metadata.symbol = placeholder,
+            "uri" => token_metadata.uri = placeholder,
+            _ => {
+                // Only keys that exist are masked, no key is added to the read
+                if let Some(key) = suffix.strip_prefix("key.") {
+                    if find_key_value(&token_metadata, key).is_some() {
+                        token_metadata.update(Field::Key(key.to_string()), placeholder);
+                    }
+                }
+            }
+        }
+    }
+    Ok(token_metadata)
+}
+
+// Deserialize the TokenMetadata stored in the metadata account
+fn load_token_metadata(metadata: &AccountInfo) -> Result<TokenMetadata> {
+    check_metadata_initialized(metadata)?;
+    let buffer = metadata.try_borrow_data()?;
+    let state = TlvStateBorrowed::unpack(&buffer)?;
+    Ok(state.get_first_variable_len_value::<TokenMetadata>()?)
+}
+
+// Metadata accounts are created by this program and never empty.
+// Check up front so reads before initialize fail with a clear error.
+fn check_metadata_initialized(metadata: &AccountInfo) -> Result<()> {
+    if metadata.owner != &id() || metadata.data_is_empty() {
+        return err!(MetadataError::MetadataNotInitialized);
+    }
+    Ok(())
+}
+
+// Load the metadata, check the update authority, apply the change and write it back
+fn apply_update(
+    accounts: &UpdateMetadata,
+    update: impl FnOnce(&mut TokenMetadata) -> Result<()>,
+) -> Result<()> {
+    let mut token_metadata = load_token_metadata(&accounts.metadata)?;
+    check_update_authority(
+        &token_metadata,
+        accounts.update_authority.key,
+        accounts.instructions.as_deref(),
+    )?;
+
+    update(&mut token_metadata)?;
+    finalize_metadata(&mut token_metadata)?;
+    msg!("TokenMetadata: {:?}", token_metadata);
+
+    realloc_metadata(
+        &accounts.metadata,
+        &accounts.payer,
+        &accounts.system_program.to_account_info(),
+        &token_metadata,
+        false,
+    )
+}
+
+// Look up the value of a custom key, failing if it doesn't exist
+fn get_key_value(token_metadata: &TokenMetadata, key: &str) -> Result<String> {
+    find_key_value(token_metadata, key)
+        .map(str::to_string)
+        .ok_or_else(|| ProgramError::Custom(TokenMetadataError::KeyNotFound as u32).into())
+}
+
+// Look up the value of a custom key
+fn find_key_value<'a>(token_metadata: &'a TokenMetadata, key: &str) -> Option<&'a str> {
+    token_metadata
+        .additional_metadata
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+// Bitmask of the flags set in the metadata, 0 if none are
+fn read_flags(token_metadata: &TokenMetadata) -> Result<u32> {
+    let flags = find_key_value(token_metadata, FLAGS_KEY)
+        .map(u32::from_str)
+        .transpose()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(flags.unwrap_or_default())
+}
+
+// Check the signer is the update authority stored in the metadata
+fn check_update_authority(
+    token_metadata: &TokenMetadata,
+    signer: &Pubkey,
+    instructions: Option<&AccountInfo>,
+) -> Result<()> {
+    let update_authority = Option::<Pubkey>::from(token_metadata.update_authority).ok_or(
+        ProgramError::Custom(TokenMetadataError::ImmutableMetadata as u32),
+    )?;
+    msg!("Update authority: {:?}", update_authority);
+    if update_authority != *signer {
+        return Err(
+            ProgramError::Custom(TokenMetadataError::IncorrectUpdateAuthority as u32).into(),
+        );
+    }
+    check_authority_program(token_metadata, instructions)
+}
+
+// Check changes come through the authority program, if one is set. Without the instructions
+// sysvar the caller is unknown, so the change is rejected.
+// It can't loop back into the change. The runtime rejects reentrancy other than direct
+// self-recursion, and neither the system and token programs this one invokes nor its own
+// `emit_cpi!` event instruction, which only logs, call an update
+fn check_authority_program(
+    token_metadata: &TokenMetadata,
+    instructions: Option<&AccountInfo>,
+) -> Result<()> {
+    let authority_program = find_key_value(token_metadata, AUTHORITY_PROGRAM_KEY)
+        .and_then(|program| Pubkey::from_str(program).ok());
+    if let Some(authority_program) = authority_program {
+        let instructions = instructions.ok_or(MetadataError::NotInvokedByAuthorityProgram)?;
+        check_invoked_by(instructions, &authority_program)?;
+    }
+    Ok(())
+}
+
+// Check the signer is the update authority or its delegate, returns whether it is the delegate
+fn check_update_authority_or_delegate(
+    token_metadata: &TokenMetadata,
+    signer: &Pubkey,
+    instructions: Option<&AccountInfo>,
+) -> Result<bool> {
+    let update_authority = Option::<Pubkey>::from(token_metadata.update_authority);
+    let is_delegate = update_authority.is_some()
+        && update_authority != Some(*signer)
+        && find_key_value(token_metadata, DELEGATE_KEY) == Some(signer.to_string().as_str());
+    if is_delegate {
+        check_authority_program(token_metadata, instructions)?;
+    } else {
+        check_update_authority(token_metadata, signer, instructions)?;
+    }
+    Ok(is_delegate)
+}
+
+// Check the current instruction is a CPI from a top level instruction of the given program
+fn check_invoked_by(instructions: &AccountInfo, program_id: &Pubkey) -> Result<()> {
+    if cpi_caller(instructions)? != Some(*program_id) {
+        return err!(MetadataError::NotInvokedByAuthorityProgram);
+    }
+    Ok(())
+}
+
+// Whether the current instruction runs through CPI rather than as a top level instruction.
+// The sysvar alone can't tell, a self-CPI shares the top level instruction of a direct call,
+// so it's only checked to be the instructions sysvar and the stack height decides
+fn is_called_via_cpi(instructions: &AccountInfo) -> Result<bool> {
+    load_current_index_checked(instructions)?;
+    Ok(get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT)
+}
+
+// Program of the top level instruction the current CPI runs under, None for a direct call.
+// The current index stays at the top level instruction through CPI, so with nested CPIs
+// this is the outermost program rather than the immediate caller
+fn cpi_caller(instructions: &AccountInfo) -> Result<Option<Pubkey>> {
+    if !is_called_via_cpi(instructions)? {
+        return Ok(None);
+    }
+    let index = load_current_index_checked(instructions)?;
+    let instruction = load_instruction_at_checked(index as usize, instructions)?;
+    Ok(Some(instruction.program_id))
+}
+
+// Message the update authority signs for a relayed update,
+// binding this program, the mint and the nonce to the update
+fn relayed_update_message(mint: &Pubkey, nonce: u64, data: &UpdateFieldData) -> Result<Vec<u8>> {
+    let mut message = Vec::new();
+    message.extend_from_slice(id().as_ref());
+    message.extend_from_slice(mint.as_ref());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    data.serialize(&mut message)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    Ok(message)
+}
+
+// Hash of the metadata an attester signs, without the keys that change on every write
+// or record the attestation itself
+fn attestation_hash(token_metadata: &TokenMetadata) -> Result<Hash> {
+    let mut canonical = token_metadata.clone();
+    for key in [
+        INTEGRITY_KEY,
+        UPDATED_SLOT_KEY,
+        ATTESTED_BY_KEY,
+        ATTESTED_HASH_KEY,
+    ] {
+        canonical.remove_key(key);
+    }
+    let serialized = canonical
+        .try_to_vec()
+        .map_err(|_| MetadataError::MetadataSerializationFailed)?;
+    Ok(hash(&serialized))
+}
+
+// Message the attester signs, binding this program and the mint to the attested content
+fn attestation_message(mint: &Pubkey, hash: &Hash) -> Vec<u8> {
+    [id().as_ref(), mint.as_ref(), hash.as_ref()].concat()
+}
+
+// Signer of the message, verified by an Ed25519 program instruction just before this one
+fn verify_ed25519_signer(instructions: &AccountInfo, message: &[u8]) -> Result<Pubkey> {
+    let index = load_current_index_checked(instructions)?;
+    let previous = index
+        .checked_sub(1)
+        .ok_or(MetadataError::InvalidSignedUpdate)?;
+    let instruction = load_instruction_at_checked(previous as usize, instructions)?;
+    require_keys_eq!(
+        instruction.program_id,
+        ed25519_program::ID,
+        MetadataError::InvalidSignedUpdate
+    );
+
+    // One signature, with its offsets all pointing into the Ed25519 instruction itself
+    let data = &instruction.data;
+    let read_u16 = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .ok_or(MetadataError::InvalidSignedUpdate)
+    };
+    require!(data.first() == Some(&1), MetadataError::InvalidSignedUpdate);
+    for instruction_index_offset in [4, 8, 14] {
+        require!(
+            read_u16(instruction_index_offset)? == u16::MAX,
+            MetadataError::InvalidSignedUpdate
+        );
+    }
+    let public_key_offset = read_u16(6)? as usize;
+    let message_offset = read_u16(10)? as usize;
+    let message_size = read_u16(12)? as usize;
+
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(MetadataError::InvalidSignedUpdate)?;
+    require!(
+        signed_message == message,
+        MetadataError::InvalidSignedUpdate
+    );
+    data.get(public_key_offset..public_key_offset + 32)
+        .and_then(|bytes| Pubkey::try_from(bytes).ok())
+        .ok_or_else(|| error!(MetadataError::InvalidSignedUpdate))
+}
+
+// Check the signer is the mint authority, which is absent if minting was disabled
+fn check_mint_authority(mint: &Mint, signer: &Pubkey) -> Result<()> {
+    let mint_authority =
+        Option::<Pubkey>::from(mint.mint_authority).ok_or(MetadataError::MintAuthorityRevoked)?;
+    if mint_authority != *signer {
+        return Err(ProgramError::Custom(TokenMetadataError::IncorrectMintAuthority as u32).into());
+    }
+    Ok(())
+}
+
+// Approvals are only required once enabled in the program config,
+// both accounts are PDAs of this program so only the discriminator needs checking
+fn check_mint_approval(config: &AccountInfo, approval: &AccountInfo, mint: &Pubkey) -> Result<()> {
+    if config.data_is_empty() {
+        return Ok(());
+    }
+    let config = Config::try_deserialize(&mut &config.try_borrow_data()?[..])?;
+    if !config.require_approval {
+        return Ok(());
+    }
+    let approval = MintApproval::try_deserialize(&mut &approval.try_borrow_data()?[..])
+        .map_err(|_| error!(MetadataError::MintNotApproved))?;
+    require_keys_eq!(approval.mint, *mint, MetadataError::MintNotApproved);
+    Ok(())
+}
+
+// Append the mint to the current registry page, creating the page when it is first used
+fn append_to_registry<'info>(
+    registry: &mut Registry,
+    registry_page: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    mint: Pubkey,
+) -> Result<()> {
+    let page = u32::try_from(registry.count / REGISTRY_PAGE_SIZE as u64)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    let page_seed = page.to_le_bytes();
+    let (expected, bump) = Pubkey::find_program_address(&[b"registry", &page_seed], &id());
+    if registry_page.key != &expected {
+        return Err(ProgramError::InvalidSeeds.into());
+    }
+
+    if registry_page.data_is_empty() {
+        let space = 8 + RegistryPage::INIT_SPACE;
+        create_account(
+            CpiContext::new(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: registry_page.clone(),
+                },
+            )
+            .with_signer(&[&[b"registry", &page_seed, &[bump]]]),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &id(),
+        )?;
+        RegistryPage { mints: Vec::new() }
+            .try_serialize(&mut &mut registry_page.try_borrow_mut_data()?[..])?;
+    }
+
+    let mut page_data = RegistryPage::try_deserialize(&mut &registry_page.try_borrow_data()?[..])?;
+    page_data.mints.push(mint);
+    page_data.try_serialize(&mut &mut registry_page.try_borrow_mut_data()?[..])?;
+
+    registry.count += 1;
+    msg!("Registered mint {} on page {}", mint, page);
+    Ok(())
+}
+
+// Accept absolute URIs of the form `scheme://rest` that fit in a value
+fn check_uri(uri: &str) -> Result<()> {
+    let is_scheme = |scheme: &str| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    };
+    match uri.split_once("://") {
+        Some((scheme, rest)) if is_scheme(scheme) && !rest.is_empty() => {}
+        _ => return err!(MetadataError::InvalidUri),
+    }
+    require!(uri.len() <= MAX_VALUE_LEN, MetadataError::ValueTooLong);
+    Ok(())
+}
+
+// Accept a single JSON value as in RFC 8259, surrounded by optional whitespace
+fn check_json(json: &str) -> Result<()> {
+    require!(json.len() <= MAX_VALUE_LEN, MetadataError::ValueTooLong);
+    let bytes = json.as_bytes();
+    let end = skip_json_value(bytes, skip_json_whitespace(bytes, 0), 0)
+        .map(|i| skip_json_whitespace(bytes, i));
+    require!(end == Some(bytes.len()), MetadataError::InvalidJson);
+    Ok(())
+}
+
+// Index after the value starting at `i`, None if it isn't valid JSON
+fn skip_json_value(bytes: &[u8], i: usize, depth: usize) -> Option<usize> {
+    if depth >= MAX_JSON_DEPTH {
+        return None;
+    }
+    match *bytes.get(i)? {
+        b'{' => skip_json_items(bytes, i + 1, b'}', |i| {
+            let i = skip_json_whitespace(bytes, skip_json_string(bytes, i)?);
+            (bytes.get(i) == Some(&b':')).then_some(())?;
+            skip_json_value(bytes, skip_json_whitespace(bytes, i + 1), depth + 1)
+        }),
+        b'[' => skip_json_items(bytes, i + 1, b']', |i| skip_json_value(bytes, i, depth + 1)),
+        b'"' => skip_json_string(bytes, i),
+        b'-' | b'0'..=b'9' => skip_json_number(bytes, i),
+        _ => [&b"true"[..], b"false", b"null"]
+            .into_iter()
+            .find(|literal| bytes[i..].starts_with(literal))
+            .map(|literal| i + literal.len()),
+    }
+}
+
+// Comma separated items up to the closing byte, `i` is past the opening byte
+fn skip_json_items(
+    bytes: &[u8],
+    i: usize,
+    close: u8,
+    skip_item: impl Fn(usize) -> Option<usize>,
+) -> Option<usize> {
+    let mut i = skip_json_whitespace(bytes, i);
+    if bytes.get(i) == Some(&close) {
+        return Some(i + 1);
+    }
+    loop {
+        i = skip_json_whitespace(bytes, skip_item(i)?);
+        match *bytes.get(i)? {
+            b',' => i = skip_json_whitespace(bytes, i + 1),
+            byte if byte == close => return Some(i + 1),
+            _ => return None,
+        }
+    }
+}
+
+fn skip_json_string(bytes: &[u8], i: usize) -> Option<usize> {
+    (bytes.get(i) == Some(&b'"')).then_some(())?;
+    let mut i = i + 1;
+    loop {
+        match *bytes.get(i)? {
+            b'"' => return Some(i + 1),
+            b'\\' => match *bytes.get(i + 1)? {
+                b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => i += 2,
+                b'u' if bytes.get(i + 2..i + 6)?.iter().all(u8::is_ascii_hexdigit) => i += 6,
+                _ => return None,
+            },
+            // Control characters must be escaped
+            0..=0x1f => return None,
+            _ => i += 1,
+        }
+    }
+}
+
+fn skip_json_number(bytes: &[u8], i: usize) -> Option<usize> {
+    let mut i = i + usize::from(bytes.get(i) == Some(&b'-'));
+    // No leading zeros
+    i = match bytes.get(i)? {
+        b'0' => i + 1,
+        _ => skip_json_digits(bytes, i)?,
+    };
+    if bytes.get(i) == Some(&b'.') {
+        i = skip_json_digits(bytes, i + 1)?;
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        i += usize::from(matches!(bytes.get(i), Some(b'+' | b'-')));
+        i = skip_json_digits(bytes, i)?;
+    }
+    Some(i)
+}
+
+// At least one digit
+fn skip_json_digits(bytes: &[u8], i: usize) -> Option<usize> {
+    let end = i + bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+    (end > i).then_some(end)
+}
+
+fn skip_json_whitespace(bytes: &[u8], i: usize) -> usize {
+    i + bytes[i..]
+        .iter()
+        .take_while(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+        .count()
+}
+
+// Accept tags like `ja` or `zh-Hant-TW`, a 2-3 letter language and alphanumeric subtags
+fn check_locale(locale: &str) -> Result<()> {
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let is_valid = locale.len() <= MAX_LOCALE_LEN
+        && (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if !is_valid {
+        return err!(MetadataError::InvalidLocale);
+    }
+    Ok(())
+}
+
+// Reserved keys storing the localized name and symbol
+fn localized_keys(locale: &str) -> (String, String) {
+    (
+        format!("{LOCALE_KEY_PREFIX}{locale}.name"),
+        format!("{LOCALE_KEY_PREFIX}{locale}.symbol"),
+    )
+}
+
+// Check the MIME type is `type/subtype`, made of RFC 6838 restricted names without parameters
+fn check_mime_type(mime: &str) -> Result<()> {
+    let is_restricted_name = |name: &str| {
+        !name.is_empty()
+            && name.len() <= 127
+            && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+    match mime.split_once('/') {
+        Some((type_, subtype)) if is_restricted_name(type_) && is_restricted_name(subtype) => {
+            Ok(())
+        }
+        _ => err!(MetadataError::InvalidMimeType),
+    }
+}
+
+// Address of the claim on a symbol
+fn symbol_claim_address(symbol: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"symbol", hash(symbol.as_bytes()).as_ref()], &id())
+}
+
+// Claim the symbol for the mint, failing if another mint holds it
+fn claim_symbol<'info>(
+    symbol_claim: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    symbol: &str,
+    mint: Pubkey,
+) -> Result<()> {
+    let (expected, bump) = symbol_claim_address(symbol);
+    if symbol_claim.key != &expected {
+        return Err(ProgramError::InvalidSeeds.into());
+    }
+    if !symbol_claim.data_is_empty() {
+        return err!(MetadataError::SymbolAlreadyTaken);
+    }
+
+    let space = 8 + SymbolClaim::INIT_SPACE;
+    let symbol_hash = hash(symbol.as_bytes());
+    create_account(
+        CpiContext::new(
+            system_program.clone(),
+            CreateAccount {
+                from: payer.clone(),
+                to: symbol_claim.clone(),
+            },
+        )
+        .with_signer(&[&[b"symbol", symbol_hash.as_ref(), &[bump]]]),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &id(),
+    )?;
+    SymbolClaim { mint }.try_serialize(&mut &mut symbol_claim.try_borrow_mut_data()?[..])?;
+    msg!("Claimed symbol {}", symbol);
+    Ok(())
+}
+
+// Check the symbol still matches its claim, if the mint claimed one.
+// The claim is only freed with the metadata, so a claimed symbol can't change
+fn check_symbol_claim(token_metadata: &TokenMetadata) -> Result<()> {
+    let claimed = find_key_value(token_metadata, SYMBOL_CLAIM_KEY);
+    if matches!(claimed, Some(symbol) if symbol != token_metadata.symbol) {
+        return err!(MetadataError::SymbolClaimed);
+    }
+    Ok(())
+}
+
+// Close a program owned account, transferring its lamports to the destination
+fn close_account<'info>(
+    account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    let lamports = account.lamports();
+    account.sub_lamports(lamports)?;
+    destination.add_lamports(lamports)?;
+    account.assign(&System::id());
+    account.realloc(0, false)?;
+    Ok(())
+}
+
+// Hash the canonical serialization of the metadata, excluding the integrity key itself
+fn compute_integrity_hash(token_metadata: &TokenMetadata) -> Result<Hash> {
+    let mut canonical = token_metadata.clone();
+    canonical.remove_key(INTEGRITY_KEY);
+    let serialized = canonical
+        .try_to_vec()
+        .map_err(|_| MetadataError::MetadataSerializationFailed)?;
+    Ok(hash(&serialized))
+}
+
+// Check the custom keys passed at initialize, before anything is allocated.
+// A repeated key would silently keep only the last value, so it's rejected too
+fn check_initial_keys<'a>(entries: impl Iterator<Item = (&'a str, &'a str)>) -> Result<()> {
+    let mut keys = Vec::new();
+    for (key, value) in entries {
+        check_key_not_reserved(key)?;
+        check_key_not_core_field(key)?;
+        require!(value.len() <= MAX_VALUE_LEN, MetadataError::ValueTooLong);
+        keys.push(key);
+    }
+    keys.sort_unstable();
+    require!(
+        keys.windows(2).all(|pair| pair[0] != pair[1]),
+        MetadataError::DuplicateKey
+    );
+    Ok(())
+}
+
+// Reject custom keys with the reserved prefix
+fn check_key_not_reserved(key: &str) -> Result<()> {
+    if key.starts_with(RESERVED_KEY_PREFIX) {
+        return err!(MetadataError::ReservedKey);
+    }
+    Ok(())
+}
+
+// Reject custom keys named like a core field, which would look like the field to readers
+fn check_key_not_core_field(key: &str) -> Result<()> {
+    if ["name", "symbol", "uri"].contains(&key) {
+        return err!(MetadataError::KeyCollidesWithCoreField);
+    }
+    Ok(())
+}
+
+// Enforce the stored policy and refresh the integrity hash, must be called after every mutation
+fn finalize_metadata(token_metadata: &mut TokenMetadata) -> Result<()> {
+    // Every write goes through here, so this also covers initialize with a past lock time
+    check_time_lock(token_metadata)?;
+    enforce_policy(token_metadata)?;
+    check_symbol_claim(token_metadata)?;
+    record_updated_slot(token_metadata)?;
+    update_integrity_hash(token_metadata)?;
+    // Measured last, the integrity hash is part of the packed size
+    check_max_total_size(token_metadata)
+}
+
+// Recompute the integrity hash
+fn update_integrity_hash(token_metadata: &mut TokenMetadata) -> Result<()> {
+    let integrity_hash = compute_integrity_hash(token_metadata)?;
+    token_metadata.update(
+        Field::Key(INTEGRITY_KEY.to_string()),
+        integrity_hash.to_string(),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use solana_program::{
+        entrypoint::{deserialize, BPF_ALIGN_OF_U128, NON_DUP_MARKER, SUCCESS},
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        system_program,
+        sysvar::{
+            self,
+            instructions::{construct_instructions_data, store_current_index, BorrowedInstruction},
+        },
+    };
+    use std::cell::Cell;
+
+    // Fails the way a writer error would while borsh counts the packed bytes
+    struct FailingSerialize;
+
+    impl AnchorSerialize for FailingSerialize {
+        fn serialize<W: std::io::Write>(&self, _writer: &mut W) -> std::io::Result<()> {
+            Err(std::io::Error::other("mock failure"))
+        }
+    }
+
+    #[test]
+    fn error_codes_stay_below_interface_errors() {
+        // The last variant, so every code of this program is below the interface range
+        assert!(
+            u32::from(MetadataError::DuplicateMember) < TokenMetadataError::IncorrectAccount as u32
+        );
+    }
+
+    #[test]
+    fn packed_len_failure_maps_to_serialization_failed() {
+        assert_eq!(
+            metadata_packed_len(&FailingSerialize).unwrap_err(),
+            MetadataError::MetadataSerializationFailed.into()
+        );
+    }
+
+    thread_local! {
+        // Per test thread, the stubs are shared by the whole test binary
+        static STACK_HEIGHT: Cell<usize> = const { Cell::new(TRANSACTION_LEVEL_STACK_HEIGHT) };
+    }
+
+    // The syscalls these tests reach: the rent sysvar and the system program transfer of
+    // realloc_metadata, and the stack height of the CPI detection
+    struct TestStubs;
+
+    impl SyscallStubs for TestStubs {
+        fn sol_get_stack_height(&self) -> u64 {
+            STACK_HEIGHT.with(Cell::get) as u64
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            SUCCESS
+        }
+
+        fn sol_invoke_signed(
+            &self,
+            instruction: &solana_program::instruction::Instruction,
+            account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> solana_program::entrypoint::ProgramResult {
+            // SystemInstruction::Transfer, a u32 tag of 2 then the u64 lamports
+            let lamports = match instruction.data.split_at(4) {
+                ([2, 0, 0, 0], lamports) => u64::from_le_bytes(lamports.try_into().unwrap()),
+                _ => return Err(ProgramError::InvalidInstructionData),
+            };
+            let account = |index: usize| {
+                account_infos
+                    .iter()
+                    .find(|info| info.key == &instruction.accounts[index].pubkey)
+                    .unwrap()
+            };
+            **account(0).try_borrow_mut_lamports()? -= lamports;
+            **account(1).try_borrow_mut_lamports()? += lamports;
+            Ok(())
+        }
+    }
+
+    // Accounts in the loader's input layout, so reallocs find the original length before the
+    // key and room after the data. Backed by u64s for the alignment the layout assumes
+    fn serialize_accounts(accounts: &[(&Pubkey, u64, &[u8])]) -> Vec<u64> {
+        let mut input = Vec::new();
+        input.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+        for (owner, lamports, data) in accounts {
+            input.extend_from_slice(&[NON_DUP_MARKER, 0, 1, 0, 0, 0, 0, 0]);
+            input.extend_from_slice(Pubkey::new_unique().as_ref());
+            input.extend_from_slice(owner.as_ref());
+            input.extend_from_slice(&lamports.to_le_bytes());
+            input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            input.extend_from_slice(data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+            input.extend_from_slice(&0u64.to_le_bytes());
+        }
+        input.extend_from_slice(&0u64.to_le_bytes());
+        input.extend_from_slice(id().as_ref());
+        input.resize(input.len().next_multiple_of(8), 0);
+        input
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    // Metadata account data holding the TokenMetadata, as initialize writes it
+    fn packed_account_data(token_metadata: &TokenMetadata) -> Vec<u8> {
+        let mut data = vec![0; metadata_tlv_size(token_metadata).unwrap()];
+        let mut state = TlvStateMut::unpack(&mut data).unwrap();
+        state
+            .alloc::<TokenMetadata>(metadata_packed_len(token_metadata).unwrap(), false)
+            .unwrap();
+        state.pack_first_variable_len_value(token_metadata).unwrap();
+        data
+    }
+
+    fn token_metadata() -> impl Strategy<Value = TokenMetadata> {
+        (
+            any::<[u8; 32]>(),
+            any::<[u8; 32]>(),
+            "\\PC{0,32}",
+            "\\PC{0,10}",
+            "\\PC{0,64}",
+            prop::collection::vec(("\\PC{1,16}", "\\PC{0,32}"), 0..8),
+        )
+            .prop_map(
+                |(update_authority, mint, name, symbol, uri, additional_metadata)| TokenMetadata {
+                    // All zeros is no update authority, as it is stored
+                    update_authority: OptionalNonZeroPubkey::try_from(Some(
+                        Pubkey::new_from_array(update_authority),
+                    ))
+                    .unwrap_or_default(),
+                    mint: Pubkey::new_from_array(mint),
+                    name,
+                    symbol,
+                    uri,
+                    additional_metadata,
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn realloc_metadata_round_trips(before in token_metadata(), after in token_metadata()) {
+            set_syscall_stubs(Box::new(TestStubs));
+            let rent = Rent::default();
+            let data = packed_account_data(&before);
+            let lamports = rent.minimum_balance(data.len());
+            let payer_lamports = 1_000_000_000;
+            let mut input = serialize_accounts(&[
+                (&id(), lamports, &data),
+                (&system_program::ID, payer_lamports, &[]),
+            ]);
+            let (_, accounts, _) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+            let (metadata, payer) = (&accounts[0], &accounts[1]);
+
+            // Grows, shrinks or packs in place, and always reads back what was written
+            prop_assert_eq!(load_token_metadata(metadata).unwrap(), before);
+            realloc_metadata(metadata, payer, payer, &after, false).unwrap();
+            prop_assert_eq!(load_token_metadata(metadata).unwrap(), after.clone());
+            prop_assert_eq!(metadata.data_len(), metadata_tlv_size(&after).unwrap());
+
+            // Rent exempt at the new size, the difference moved to or from the payer
+            prop_assert_eq!(metadata.lamports(), rent.minimum_balance(metadata.data_len()));
+            prop_assert_eq!(
+                metadata.lamports() + payer.lamports(),
+                lamports + payer_lamports
+            );
+        }
+    }
+
+    // Run the CPI detection at the stack height, under the top level instructions of the
+    // programs with the current index at the last one
+    fn detect_cpi(
+        stack_height: usize,
+        programs: &[Pubkey],
+        key: &Pubkey,
+    ) -> Result<(bool, Option<Pubkey>)> {
+        set_syscall_stubs(Box::new(TestStubs));
+        STACK_HEIGHT.with(|height| height.set(stack_height));
+
+        let instructions: Vec<BorrowedInstruction> = programs
+            .iter()
+            .map(|program_id| BorrowedInstruction {
+                program_id,
+                accounts: Vec::new(),
+                data: &[],
+            })
+            .collect();
+        let mut data = construct_instructions_data(&instructions);
+        store_current_index(&mut data, programs.len() as u16 - 1);
+        let mut lamports = 0;
+        let owner = sysvar::ID;
+        let account = AccountInfo::new(
+            key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        Ok((is_called_via_cpi(&account)?, cpi_caller(&account)?))
+    }
+
+    #[test]
+    fn top_level_call_has_no_cpi_caller() {
+        // Another top level instruction first, so the current index isn't 0
+        let programs = [ed25519_program::ID, id()];
+        assert_eq!(
+            detect_cpi(
+                TRANSACTION_LEVEL_STACK_HEIGHT,
+                &programs,
+                &sysvar::instructions::ID
+            )
+            .unwrap(),
+            (false, None)
+        );
+    }
+
+    #[test]
+    fn direct_cpi_reports_the_calling_program() {
+        let caller = Pubkey::new_unique();
+        let programs = [ed25519_program::ID, caller];
+        assert_eq!(
+            detect_cpi(
+                TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+                &programs,
+                &sysvar::instructions::ID
+            )
+            .unwrap(),
+            (true, Some(caller))
+        );
+    }
+
+    #[test]
+    fn nested_cpi_reports_the_outermost_program() {
+        // Outer invokes an intermediate program, which invokes this one
+        let outer = Pubkey::new_unique();
+        assert_eq!(
+            detect_cpi(
+                TRANSACTION_LEVEL_STACK_HEIGHT + 2,
+                &[outer],
+                &sysvar::instructions::ID
+            )
+            .unwrap(),
+            (true, Some(outer))
+        );
+    }
+
+    #[test]
+    fn cpi_detection_rejects_other_accounts() {
+        assert_eq!(
+            detect_cpi(
+                TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+                &[Pubkey::new_unique()],
+                &Pubkey::new_unique()
+            )
+            .unwrap_err(),
+            ProgramError::UnsupportedSysvar.into()
+        );
     }
 }
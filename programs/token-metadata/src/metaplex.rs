@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use spl_token_metadata_interface::state::{Field, TokenMetadata};
+
+use crate::{error::MetadataError, find_key_value};
+
+/// Reserved key storing the royalty in basis points
+pub const SELLER_FEE_BASIS_POINTS_KEY: &str = "__royalty.seller_fee_basis_points";
+
+/// Reserved key storing the creators as comma separated `address:share` pairs
+pub const CREATORS_KEY: &str = "__royalty.creators";
+
+/// Maximum number of creators, matching Metaplex
+pub const MAX_CREATORS: usize = 5;
+
+/// Creator sharing in the royalties
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Creator {
+    pub address: Pubkey,
+    /// Percentage of the royalties, the shares of all creators add up to 100
+    pub share: u8,
+}
+
+/// Creator as laid out in Metaplex `Creator`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetaplexCreator {
+    pub address: Pubkey,
+    /// Always false, creators are not verified by this program
+    pub verified: bool,
+    pub share: u8,
+}
+
+/// Metadata laid out as the leading fields of Metaplex `DataV2`,
+/// collection and uses are not tracked by this program
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MetaplexData {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<MetaplexCreator>>,
+}
+
+// Store the royalties as reserved keys
+pub fn write_royalties(
+    token_metadata: &mut TokenMetadata,
+    seller_fee_basis_points: u16,
+    creators: &[Creator],
+) -> Result<()> {
+    require!(
+        seller_fee_basis_points <= 10_000,
+        MetadataError::InvalidRoyalties
+    );
+    require!(
+        creators.len() <= MAX_CREATORS,
+        MetadataError::InvalidRoyalties
+    );
+    let total_share: u32 = creators.iter().map(|creator| creator.share as u32).sum();
+    require!(
+        creators.is_empty() || total_share == 100,
+        MetadataError::InvalidRoyalties
+    );
+
+    token_metadata.update(
+        Field::Key(SELLER_FEE_BASIS_POINTS_KEY.to_string()),
+        seller_fee_basis_points.to_string(),
+    );
+    if creators.is_empty() {
+        token_metadata.remove_key(CREATORS_KEY);
+    } else {
+        let encoded = creators
+            .iter()
+            .map(|creator| format!("{}:{}", creator.address, creator.share))
+            .collect::<Vec<_>>()
+            .join(",");
+        token_metadata.update(Field::Key(CREATORS_KEY.to_string()), encoded);
+    }
+    Ok(())
+}
+
+// Map the metadata to the Metaplex field layout
+pub fn to_metaplex(token_metadata: &TokenMetadata) -> Result<MetaplexData> {
+    let seller_fee_basis_points = find_key_value(token_metadata, SELLER_FEE_BASIS_POINTS_KEY)
+        .map(str::parse::<u16>)
+        .transpose()
+        .map_err(|_| ProgramError::InvalidAccountData)?
+        .unwrap_or_default();
+    let creators = find_key_value(token_metadata, CREATORS_KEY)
+        .map(|encoded| {
+            encoded
+                .split(',')
+                .map(parse_creator)
+                .collect::<Option<Vec<_>>>()
+                .ok_or(ProgramError::InvalidAccountData)
+        })
+        .transpose()?;
+
+    Ok(MetaplexData {
+        name: token_metadata.name.clone(),
+        symbol: token_metadata.symbol.clone(),
+        uri: token_metadata.uri.clone(),
+        seller_fee_basis_points,
+        creators,
+    })
+}
+
+// Parse an `address:share` pair
+fn parse_creator(encoded: &str) -> Option<MetaplexCreator> {
+    let (address, share) = encoded.split_once(':')?;
+    Some(MetaplexCreator {
+        address: address.parse().ok()?,
+        verified: false,
+        share: share.parse().ok()?,
+    })
+}
@@ -0,0 +1,517 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use spl_token_metadata_interface::state::{Field, TokenMetadata};
+
+use std::str::FromStr;
+
+use crate::{
+    error::MetadataError, find_key_value, metadata_tlv_size, AnchorField, RESERVED_KEY_PREFIX,
+};
+
+/// Reserved key storing how symbols that are not uppercase are handled
+pub const UPPERCASE_SYMBOL_KEY: &str = "__uppercase_symbol";
+
+/// Reserved key storing how the symbol length is measured
+pub const SYMBOL_LENGTH_KEY: &str = "__symbol_length";
+
+/// Maximum length of the symbol under the symbol length policy, in bytes or chars
+pub const MAX_SYMBOL_LEN: usize = 10;
+
+/// Reserved keys storing the minimum byte length of the name, and of the symbol
+/// as measured by the symbol length policy
+pub const MIN_NAME_LEN_KEY: &str = "__min_name_len";
+pub const MIN_SYMBOL_LEN_KEY: &str = "__min_symbol_len";
+
+/// Reserved key storing how names longer than `MAX_NAME_LEN` are handled
+pub const NAME_LIMIT_KEY: &str = "__name_limit";
+
+/// Maximum byte length of the name when the policy limits it
+pub const MAX_NAME_LEN: usize = 32;
+
+/// Reserved key storing the oracle allowed to write oracle keys
+pub const ORACLE_KEY: &str = "__oracle";
+
+/// Prefix of keys only the oracle can write
+pub const ORACLE_KEY_PREFIX: &str = "__oracle.";
+
+/// Prefix of keys marking a field as locked, valued `LOCK_PERMANENT` or `LOCK_REVERSIBLE`
+pub const LOCKED_KEY_PREFIX: &str = "__locked.";
+
+/// Lock that can never be removed
+pub const LOCK_PERMANENT: &str = "permanent";
+
+/// Lock the update authority can remove again
+pub const LOCK_REVERSIBLE: &str = "reversible";
+
+/// Reserved key storing the pending owner allowed to claim the update authority once
+pub const CLAIMANT_KEY: &str = "__claimant";
+
+/// Reserved key storing the program updates must be invoked from through CPI
+pub const AUTHORITY_PROGRAM_KEY: &str = "__authority_program";
+
+/// Reserved key storing the mint supply required before `update_field` is allowed
+pub const SUPPLY_THRESHOLD_KEY: &str = "__supply_threshold";
+
+/// Reserved key storing the slot of the last write, present if the policy tracks updates
+pub const UPDATED_SLOT_KEY: &str = "__updated_slot";
+
+/// Reserved key storing the unix timestamp after which the metadata can't be changed
+pub const LOCK_AT_KEY: &str = "__lock_at";
+
+/// Reserved keys storing the lamports charged for every `update_field` and their destination
+pub const UPDATE_FEE_KEY: &str = "__update_fee";
+pub const FEE_DESTINATION_KEY: &str = "__fee_destination";
+
+/// Reserved key storing the admin, the only signer allowed to change the update authority
+pub const ADMIN_KEY: &str = "__admin";
+
+/// Reserved key storing the attester trusted by `verify_attestation`
+pub const ATTESTER_KEY: &str = "__attester";
+
+/// Prefix of keys marking a custom key as allowed, if any are present other custom keys are rejected
+pub const ALLOWED_KEY_PREFIX: &str = "__allowed.";
+
+/// Reserved key storing the prefix every custom key must start with
+pub const KEY_PREFIX_KEY: &str = "__key_prefix";
+
+/// Reserved key present if mints with a freeze authority are rejected
+pub const REJECT_FREEZABLE_KEY: &str = "__reject_freezable";
+
+/// Reserved key present if an update authority without lamports is rejected
+pub const REQUIRE_FUNDED_AUTHORITY_KEY: &str = "__require_funded_authority";
+
+/// Reserved key storing the maximum size of the metadata account data, in bytes
+pub const MAX_TOTAL_SIZE_KEY: &str = "__max_total_size";
+
+/// Optional rules set at initialize and enforced on every update
+#[derive(AnchorSerialize, AnchorDeserialize, Default)]
+pub struct Policy {
+    /// Require the symbol to be uppercase ASCII
+    pub uppercase_symbol: Option<UppercaseSymbol>,
+    /// Oracle allowed to write keys under `ORACLE_KEY_PREFIX`
+    pub oracle: Option<Pubkey>,
+    /// Limit the symbol to `MAX_SYMBOL_LEN`, measured in bytes or chars
+    pub symbol_length: Option<SymbolLength>,
+    /// Owner allowed to claim the update authority from the initial authority
+    pub claimant: Option<Pubkey>,
+    /// Limit the name to `MAX_NAME_LEN` bytes
+    pub name_limit: Option<NameLimit>,
+    /// Program `update_field` must be invoked from through CPI
+    pub authority_program: Option<Pubkey>,
+    /// Mint supply required before `update_field` is allowed
+    pub supply_threshold: Option<u64>,
+    /// Record the slot of every write in `UPDATED_SLOT_KEY`
+    pub track_updates: Option<bool>,
+    /// Unix timestamp from which every change is rejected, regardless of authority
+    pub lock_at: Option<i64>,
+    /// Minimum byte length of the name, 0 if not set
+    pub min_name_len: Option<u32>,
+    /// Minimum length of the symbol, measured like `MAX_SYMBOL_LEN`, 0 if not set
+    pub min_symbol_len: Option<u32>,
+    /// Reject initialize and updates while the mint has a freeze authority. Frozen state
+    /// belongs to token accounts, so the freeze authority is what can freeze the token
+    pub reject_freezable: Option<bool>,
+    /// Custom keys allowed in the metadata, any key if empty or not set
+    pub allowed_keys: Option<Vec<String>>,
+    /// Prefix every custom key must start with, namespacing the keys of an application
+    pub key_prefix: Option<String>,
+    /// Fee charged to the payer of every `update_field`, free if zero or not set
+    pub update_fee: Option<UpdateFee>,
+    /// Attester whose signature `verify_attestation` accepts
+    pub attester: Option<Pubkey>,
+    /// Admin that changes the update authority instead of it, without editing fields
+    pub admin: Option<Pubkey>,
+    /// Reject setting an update authority without lamports, which couldn't pay its transaction fees
+    pub require_funded_authority: Option<bool>,
+    /// Maximum size of the metadata account data, capping rent even if the authority would pay
+    pub max_total_size: Option<u32>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct UpdateFee {
+    /// Lamports charged per update
+    pub lamports: u64,
+    /// Account credited with the fee
+    pub destination: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum UppercaseSymbol {
+    /// Reject symbols that are not uppercase
+    Reject,
+    /// Convert the symbol to uppercase before storing
+    Normalize,
+}
+
+impl UppercaseSymbol {
+    fn as_str(&self) -> &'static str {
+        match self {
+            UppercaseSymbol::Reject => "reject",
+            UppercaseSymbol::Normalize => "normalize",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "reject" => Some(UppercaseSymbol::Reject),
+            "normalize" => Some(UppercaseSymbol::Normalize),
+            _ => None,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolLength {
+    /// Measure the symbol in UTF-8 bytes
+    Bytes,
+    /// Measure the symbol in Unicode scalar values
+    Chars,
+}
+
+impl SymbolLength {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SymbolLength::Bytes => "bytes",
+            SymbolLength::Chars => "chars",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "bytes" => Some(SymbolLength::Bytes),
+            "chars" => Some(SymbolLength::Chars),
+            _ => None,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum NameLimit {
+    /// Reject names that are too long
+    Reject,
+    /// Truncate names that are too long at a char boundary
+    Truncate,
+}
+
+impl NameLimit {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NameLimit::Reject => "reject",
+            NameLimit::Truncate => "truncate",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "reject" => Some(NameLimit::Reject),
+            "truncate" => Some(NameLimit::Truncate),
+            _ => None,
+        }
+    }
+}
+
+// Store the policy as reserved keys so it travels with the metadata
+pub fn write_policy(token_metadata: &mut TokenMetadata, policy: &Policy) {
+    if let Some(mode) = policy.uppercase_symbol {
+        token_metadata.update(
+            Field::Key(UPPERCASE_SYMBOL_KEY.to_string()),
+            mode.as_str().to_string(),
+        );
+    }
+    if let Some(oracle) = policy.oracle {
+        token_metadata.update(Field::Key(ORACLE_KEY.to_string()), oracle.to_string());
+    }
+    if let Some(claimant) = policy.claimant {
+        token_metadata.update(Field::Key(CLAIMANT_KEY.to_string()), claimant.to_string());
+    }
+    if let Some(program) = policy.authority_program {
+        token_metadata.update(
+            Field::Key(AUTHORITY_PROGRAM_KEY.to_string()),
+            program.to_string(),
+        );
+    }
+    if let Some(threshold) = policy.supply_threshold {
+        token_metadata.update(
+            Field::Key(SUPPLY_THRESHOLD_KEY.to_string()),
+            threshold.to_string(),
+        );
+    }
+    if let Some(lock_at) = policy.lock_at {
+        token_metadata.update(Field::Key(LOCK_AT_KEY.to_string()), lock_at.to_string());
+    }
+    if let Some(min_len) = policy.min_name_len {
+        token_metadata.update(
+            Field::Key(MIN_NAME_LEN_KEY.to_string()),
+            min_len.to_string(),
+        );
+    }
+    if let Some(min_len) = policy.min_symbol_len {
+        token_metadata.update(
+            Field::Key(MIN_SYMBOL_LEN_KEY.to_string()),
+            min_len.to_string(),
+        );
+    }
+    if policy.reject_freezable == Some(true) {
+        token_metadata.update(Field::Key(REJECT_FREEZABLE_KEY.to_string()), String::new());
+    }
+    if policy.require_funded_authority == Some(true) {
+        token_metadata.update(
+            Field::Key(REQUIRE_FUNDED_AUTHORITY_KEY.to_string()),
+            String::new(),
+        );
+    }
+    if policy.track_updates == Some(true) {
+        // Set to the current slot by `record_updated_slot`
+        token_metadata.update(Field::Key(UPDATED_SLOT_KEY.to_string()), String::new());
+    }
+    if let Some(fee) = policy.update_fee.filter(|fee| fee.lamports > 0) {
+        token_metadata.update(
+            Field::Key(UPDATE_FEE_KEY.to_string()),
+            fee.lamports.to_string(),
+        );
+        token_metadata.update(
+            Field::Key(FEE_DESTINATION_KEY.to_string()),
+            fee.destination.to_string(),
+        );
+    }
+    if let Some(admin) = policy.admin {
+        token_metadata.update(Field::Key(ADMIN_KEY.to_string()), admin.to_string());
+    }
+    if let Some(attester) = policy.attester {
+        token_metadata.update(Field::Key(ATTESTER_KEY.to_string()), attester.to_string());
+    }
+    if let Some(prefix) = policy
+        .key_prefix
+        .as_ref()
+        .filter(|prefix| !prefix.is_empty())
+    {
+        token_metadata.update(Field::Key(KEY_PREFIX_KEY.to_string()), prefix.clone());
+    }
+    for key in policy.allowed_keys.iter().flatten() {
+        token_metadata.update(
+            Field::Key(format!("{ALLOWED_KEY_PREFIX}{key}")),
+            String::new(),
+        );
+    }
+    if let Some(max_size) = policy.max_total_size {
+        token_metadata.update(
+            Field::Key(MAX_TOTAL_SIZE_KEY.to_string()),
+            max_size.to_string(),
+        );
+    }
+    if let Some(mode) = policy.name_limit {
+        token_metadata.update(
+            Field::Key(NAME_LIMIT_KEY.to_string()),
+            mode.as_str().to_string(),
+        );
+    }
+    if let Some(mode) = policy.symbol_length {
+        token_metadata.update(
+            Field::Key(SYMBOL_LENGTH_KEY.to_string()),
+            mode.as_str().to_string(),
+        );
+    }
+}
+
+// Check the metadata against the policy stored in its reserved keys
+pub fn enforce_policy(token_metadata: &mut TokenMetadata) -> Result<()> {
+    let uppercase_symbol =
+        find_key_value(token_metadata, UPPERCASE_SYMBOL_KEY).and_then(UppercaseSymbol::from_str);
+    if let Some(mode) = uppercase_symbol {
+        if mode == UppercaseSymbol::Normalize {
+            token_metadata.symbol.make_ascii_uppercase();
+        }
+        let symbol = &token_metadata.symbol;
+        if !symbol.is_ascii() || symbol.bytes().any(|b| b.is_ascii_lowercase()) {
+            return err!(MetadataError::SymbolNotUppercase);
+        }
+    }
+
+    let symbol_length =
+        find_key_value(token_metadata, SYMBOL_LENGTH_KEY).and_then(SymbolLength::from_str);
+    let len = match symbol_length {
+        Some(SymbolLength::Chars) => token_metadata.symbol.chars().count(),
+        _ => token_metadata.symbol.len(),
+    };
+    if symbol_length.is_some() && len > MAX_SYMBOL_LEN {
+        return err!(MetadataError::SymbolTooLong);
+    }
+    if len < min_len(token_metadata, MIN_SYMBOL_LEN_KEY)? {
+        return err!(MetadataError::SymbolTooShort);
+    }
+    if token_metadata.name.len() < min_len(token_metadata, MIN_NAME_LEN_KEY)? {
+        return err!(MetadataError::NameTooShort);
+    }
+
+    let name_limit = find_key_value(token_metadata, NAME_LIMIT_KEY).and_then(NameLimit::from_str);
+    if let Some(mode) = name_limit {
+        let name = &mut token_metadata.name;
+        if name.len() > MAX_NAME_LEN {
+            if mode == NameLimit::Reject {
+                return err!(MetadataError::NameTooLong);
+            }
+            // Never split a multibyte char
+            let mut end = MAX_NAME_LEN;
+            while !name.is_char_boundary(end) {
+                end -= 1;
+            }
+            name.truncate(end);
+        }
+    }
+
+    // Custom keys must be on the allowlist, if there is one
+    let has_allowlist = token_metadata
+        .additional_metadata
+        .iter()
+        .any(|(key, _)| key.starts_with(ALLOWED_KEY_PREFIX));
+    if has_allowlist {
+        let is_allowed = |key: &str| {
+            key.starts_with(RESERVED_KEY_PREFIX)
+                || find_key_value(token_metadata, &format!("{ALLOWED_KEY_PREFIX}{key}")).is_some()
+        };
+        if !token_metadata
+            .additional_metadata
+            .iter()
+            .all(|(key, _)| is_allowed(key))
+        {
+            return err!(MetadataError::KeyNotAllowed);
+        }
+    }
+
+    // Custom keys must start with the prefix, if there is one
+    if let Some(prefix) = find_key_value(token_metadata, KEY_PREFIX_KEY) {
+        if !token_metadata
+            .additional_metadata
+            .iter()
+            .all(|(key, _)| key.starts_with(RESERVED_KEY_PREFIX) || key.starts_with(prefix))
+        {
+            return err!(MetadataError::KeyPrefixRequired);
+        }
+    }
+    Ok(())
+}
+
+// Record the current slot, if the policy tracks updates
+pub fn record_updated_slot(token_metadata: &mut TokenMetadata) -> Result<()> {
+    if find_key_value(token_metadata, UPDATED_SLOT_KEY).is_some() {
+        token_metadata.update(
+            Field::Key(UPDATED_SLOT_KEY.to_string()),
+            Clock::get()?.slot.to_string(),
+        );
+    }
+    Ok(())
+}
+
+// Reject metadata packing to more than the stored maximum size
+pub fn check_max_total_size(token_metadata: &TokenMetadata) -> Result<()> {
+    let max_size = find_key_value(token_metadata, MAX_TOTAL_SIZE_KEY)
+        .map(usize::from_str)
+        .transpose()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if let Some(max_size) = max_size {
+        if metadata_tlv_size(token_metadata)? > max_size {
+            return err!(MetadataError::MaxSizeExceeded);
+        }
+    }
+    Ok(())
+}
+
+// Reject changes once the clock reaches the stored lock time
+pub fn check_time_lock(token_metadata: &TokenMetadata) -> Result<()> {
+    let lock_at = find_key_value(token_metadata, LOCK_AT_KEY)
+        .map(i64::from_str)
+        .transpose()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if let Some(lock_at) = lock_at {
+        if Clock::get()?.unix_timestamp >= lock_at {
+            return err!(MetadataError::MetadataLockedByTime);
+        }
+    }
+    Ok(())
+}
+
+// Reject mints that have a freeze authority, if the policy requires it
+pub fn check_freeze_authority(token_metadata: &TokenMetadata, mint: &Mint) -> Result<()> {
+    if find_key_value(token_metadata, REJECT_FREEZABLE_KEY).is_some()
+        && mint.freeze_authority.is_some()
+    {
+        return err!(MetadataError::MintFrozen);
+    }
+    Ok(())
+}
+
+// Reject an update authority account without lamports, if the policy requires it
+pub fn check_authority_funded(
+    token_metadata: &TokenMetadata,
+    authority: &AccountInfo,
+) -> Result<()> {
+    if find_key_value(token_metadata, REQUIRE_FUNDED_AUTHORITY_KEY).is_some()
+        && authority.lamports() == 0
+    {
+        return err!(MetadataError::AuthorityUnfunded);
+    }
+    Ok(())
+}
+
+// Reject updates until the mint supply reaches the stored threshold
+pub fn check_supply_threshold(token_metadata: &TokenMetadata, supply: u64) -> Result<()> {
+    let threshold = find_key_value(token_metadata, SUPPLY_THRESHOLD_KEY)
+        .map(u64::from_str)
+        .transpose()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if matches!(threshold, Some(threshold) if supply < threshold) {
+        return err!(MetadataError::SupplyThresholdNotMet);
+    }
+    Ok(())
+}
+
+// Minimum length stored under the key, 0 if not set
+fn min_len(token_metadata: &TokenMetadata, key: &str) -> Result<usize> {
+    let min_len = find_key_value(token_metadata, key)
+        .map(usize::from_str)
+        .transpose()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(min_len.unwrap_or_default())
+}
+
+// Reserved key recording the lock of a field
+pub fn locked_key(field: &AnchorField) -> String {
+    format!("{LOCKED_KEY_PREFIX}{}", field.key_suffix())
+}
+
+// Lock a field, a permanent lock can't be downgraded to a reversible one
+pub fn lock_field(
+    token_metadata: &mut TokenMetadata,
+    field: &AnchorField,
+    permanent: bool,
+) -> Result<()> {
+    let key = locked_key(field);
+    if find_key_value(token_metadata, &key) == Some(LOCK_PERMANENT) {
+        return err!(MetadataError::FieldLocked);
+    }
+    let lock = if permanent {
+        LOCK_PERMANENT
+    } else {
+        LOCK_REVERSIBLE
+    };
+    token_metadata.update(Field::Key(key), lock.to_string());
+    Ok(())
+}
+
+// Remove a reversible lock from a field
+pub fn unlock_field(token_metadata: &mut TokenMetadata, field: &AnchorField) -> Result<()> {
+    let key = locked_key(field);
+    if find_key_value(token_metadata, &key) == Some(LOCK_PERMANENT) {
+        return err!(MetadataError::FieldLocked);
+    }
+    token_metadata.remove_key(&key);
+    Ok(())
+}
+
+// Reject changes to a locked field
+pub fn check_field_unlocked(token_metadata: &TokenMetadata, field: &AnchorField) -> Result<()> {
+    if find_key_value(token_metadata, &locked_key(field)).is_some() {
+        return err!(MetadataError::FieldLocked);
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+
+use crate::{AnchorField, UpdateFieldData};
+
+/// Number of mints stored in each registry page, a full page fits in the return data limit
+pub const REGISTRY_PAGE_SIZE: usize = 30;
+
+/// Maximum number of members of a governance
+pub const MAX_GOVERNANCE_MEMBERS: usize = 10;
+
+/// Number of records a change log keeps before overwriting the oldest
+pub const CHANGE_LOG_CAPACITY: usize = 16;
+
+/// Program wide settings managed by the admin
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    /// Admin allowed to manage mint approvals
+    pub admin: Pubkey,
+    /// Whether `initialize` requires the mint to be approved
+    pub require_approval: bool,
+}
+
+/// Marks a mint as approved to have metadata created
+#[account]
+#[derive(InitSpace)]
+pub struct MintApproval {
+    /// The approved mint
+    pub mint: Pubkey,
+}
+
+/// Counts the mints registered by `initialize`
+#[account]
+#[derive(InitSpace)]
+pub struct Registry {
+    /// Number of registered mints, the next mint goes into page `count / REGISTRY_PAGE_SIZE`
+    pub count: u64,
+}
+
+/// One page of registered mints
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryPage {
+    /// Mints in the order they were registered
+    #[max_len(REGISTRY_PAGE_SIZE)]
+    pub mints: Vec<Pubkey>,
+}
+
+/// Multisig acting as the update authority of a mint's metadata
+#[account]
+#[derive(InitSpace)]
+pub struct Governance {
+    /// Members allowed to propose and vote on updates
+    #[max_len(MAX_GOVERNANCE_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    /// Number of approvals required to execute an update
+    pub threshold: u8,
+}
+
+/// Layout version of proposals written by this build. Bump it with every change to the
+/// `VersionedProposal` layout, and extend `migrate_proposal` to upgrade the previous one
+pub const PROPOSAL_VERSION: u8 = 1;
+
+/// Pending metadata update awaiting approvals from governance members
+#[account]
+pub struct VersionedProposal {
+    /// Layout version, first so readers can tell layouts apart before decoding the rest
+    pub version: u8,
+    /// Mint whose metadata is updated
+    pub mint: Pubkey,
+    /// Member who created the proposal, refunded when it is executed
+    pub proposer: Pubkey,
+    /// Distinguishes proposals for the same mint
+    pub nonce: u64,
+    /// Approvals required, copied from the governance when proposed
+    pub threshold: u8,
+    /// Members who approved the update
+    pub approvals: Vec<Pubkey>,
+    /// Update applied once the threshold is met
+    pub data: UpdateFieldData,
+}
+
+impl VersionedProposal {
+    // Space for a proposal holding the given update, with room for every member to approve
+    pub fn space(data: &UpdateFieldData) -> usize {
+        let field_len = match &data.field {
+            AnchorField::Key(key) => 1 + 4 + key.len(),
+            _ => 1,
+        };
+        8 + 1 + 32 + 32 + 8 + 1 + 4 + 32 * MAX_GOVERNANCE_MEMBERS + field_len + 4 + data.value.len()
+    }
+}
+
+/// Proposal as written before proposals were versioned, only read by `migrate_proposal`.
+/// Its own discriminator tells it apart from a `VersionedProposal`
+#[account]
+pub struct Proposal {
+    pub mint: Pubkey,
+    pub proposer: Pubkey,
+    pub nonce: u64,
+    pub threshold: u8,
+    pub approvals: Vec<Pubkey>,
+    pub data: UpdateFieldData,
+}
+
+impl From<Proposal> for VersionedProposal {
+    fn from(proposal: Proposal) -> Self {
+        VersionedProposal {
+            version: PROPOSAL_VERSION,
+            mint: proposal.mint,
+            proposer: proposal.proposer,
+            nonce: proposal.nonce,
+            threshold: proposal.threshold,
+            approvals: proposal.approvals,
+            data: proposal.data,
+        }
+    }
+}
+
+/// Ring buffer of the latest updates to a mint's metadata, readable by other programs
+#[account]
+#[derive(InitSpace)]
+pub struct ChangeLog {
+    /// Mint whose updates are recorded
+    pub mint: Pubkey,
+    /// Number of records ever written, the next goes into `records[count % CHANGE_LOG_CAPACITY]`
+    pub count: u64,
+    /// Latest records, in ring buffer order
+    #[max_len(CHANGE_LOG_CAPACITY)]
+    pub records: Vec<ChangeRecord>,
+}
+
+/// One update recorded in a change log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ChangeRecord {
+    /// Slot of the update
+    pub slot: u64,
+    /// Hash of the updated field, `name`, `symbol`, `uri` or the custom key
+    pub field_hash: [u8; 32],
+    /// Hash of the written value
+    pub value_hash: [u8; 32],
+}
+
+impl ChangeLog {
+    // Append the record, overwriting the oldest once full
+    pub fn record(&mut self, record: ChangeRecord) {
+        let index = (self.count % CHANGE_LOG_CAPACITY as u64) as usize;
+        if index < self.records.len() {
+            self.records[index] = record;
+        } else {
+            self.records.push(record);
+        }
+        self.count += 1;
+    }
+}
+
+/// Claims a symbol for one mint, at the PDA `[b"symbol", hash(symbol)]`
+#[account]
+#[derive(InitSpace)]
+pub struct SymbolClaim {
+    /// Mint holding the symbol
+    pub mint: Pubkey,
+}
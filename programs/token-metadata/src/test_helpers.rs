@@ -0,0 +1,122 @@
+use anchor_lang::{prelude::*, Bumps};
+use anchor_spl::token_interface::Mint;
+use solana_program::{hash::hash, program::set_return_data};
+use std::collections::BTreeSet;
+
+use crate::{check_mint_authority, cpi_caller};
+
+// Test-only instructions, built with the `test-helpers` feature only. Anchor generates client
+// code for every instruction in the program module, which can't be configured out, so these
+// are dispatched from the fallback instead and stay out of the production program and its IDL.
+// Clients build them from the Anchor discriminator of the name and the borsh arguments
+pub fn dispatch<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> Option<Result<()>> {
+    let tag = data.get(..8)?;
+    if tag == discriminator("corrupt_for_test") {
+        Some(corrupt_for_test(program_id, accounts, data))
+    } else if tag == discriminator("get_cpi_caller") {
+        Some(get_cpi_caller(program_id, accounts, data))
+    } else {
+        None
+    }
+}
+
+// Lets integration tests put the account in a corrupt state
+fn corrupt_for_test<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> Result<()> {
+    let CorruptForTestArgs { offset, bytes } = CorruptForTestArgs::deserialize(&mut &data[8..])
+        .map_err(|_| ErrorCode::InstructionDidNotDeserialize)?;
+    with_context::<CorruptForTest>(program_id, accounts, data, |ctx| {
+        check_mint_authority(&ctx.accounts.mint, ctx.accounts.mint_authority.key)?;
+
+        let mut data = ctx.accounts.metadata.try_borrow_mut_data()?;
+        let start = offset as usize;
+        let end = start
+            .checked_add(bytes.len())
+            .filter(|end| *end <= data.len())
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        data[start..end].copy_from_slice(&bytes);
+        Ok(())
+    })
+}
+
+// Exposes the CPI detection to integration tests, returns the borsh Option<Pubkey>
+fn get_cpi_caller<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> Result<()> {
+    with_context::<ReadInstructions>(program_id, accounts, data, |ctx| {
+        let caller = cpi_caller(&ctx.accounts.instructions)?;
+        set_return_data(&caller.try_to_vec()?);
+        Ok(())
+    })
+}
+
+// Anchor's instruction discriminator, the first 8 bytes of sha256("global:<name>")
+fn discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{name}").as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+// Validate the accounts and run the handler, as the handlers Anchor generates do
+fn with_context<'info, T>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+    handler: impl FnOnce(Context<T>) -> Result<()>,
+) -> Result<()>
+where
+    T: Accounts<'info, T::Bumps> + AccountsExit<'info> + Bumps,
+    T::Bumps: Default,
+{
+    let mut bumps = T::Bumps::default();
+    let mut reallocs = BTreeSet::new();
+    let mut remaining_accounts = accounts;
+    let mut accounts = T::try_accounts(
+        program_id,
+        &mut remaining_accounts,
+        data,
+        &mut bumps,
+        &mut reallocs,
+    )?;
+    handler(Context::new(
+        program_id,
+        &mut accounts,
+        remaining_accounts,
+        bumps,
+    ))?;
+    accounts.exit(program_id)
+}
+
+#[derive(AnchorDeserialize)]
+struct CorruptForTestArgs {
+    offset: u32,
+    bytes: Vec<u8>,
+}
+
+#[derive(Accounts)]
+pub struct CorruptForTest<'info> {
+    /// CHECK: written as raw bytes, the content isn't checked
+    #[account(
+        mut,
+        seeds = [b"metadata", mint.key().as_ref()],
+        bump)
+    ]
+    pub metadata: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub mint_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadInstructions<'info> {
+    /// CHECK: Instructions sysvar, checked by the CPI detection
+    pub instructions: UncheckedAccount<'info>,
+}
@@ -0,0 +1,95 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithPolicy,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("admin", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const admin = Keypair.generate();
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const updateAuthority = (authority: Keypair, newAuthority: PublicKey) =>
+    program.methods
+      .updateAuthority(newAuthority)
+      .accounts({
+        metadata: metadataPDA,
+        authority: authority.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(authority === wallet.payer ? [] : [authority])
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    // The wallet edits fields, the admin manages the authority
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { admin: admin.publicKey }
+    ));
+  });
+
+  it("Rejects an authority change by the update authority", async () => {
+    try {
+      await updateAuthority(wallet.payer, Keypair.generate().publicKey);
+      expect.fail("Update authority should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("NotAdmin");
+    }
+  });
+
+  it("Rejects a field edit by the admin", async () => {
+    try {
+      await updateField(program, wallet.payer, mint, "key1", "value1", admin);
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // TokenMetadataError::IncorrectUpdateAuthority
+      expect(error.message).to.contain("0x35c2b5c0");
+    }
+  });
+
+  it("Update authority edits fields", async () => {
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Admin rotates the update authority", async () => {
+    const newAuthority = Keypair.generate();
+    await updateAuthority(admin, newAuthority.publicKey);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.updateAuthority.toBase58()).to.equal(
+      newAuthority.publicKey.toBase58()
+    );
+
+    // The new authority edits fields, the old one no longer can
+    await updateField(
+      program,
+      wallet.payer,
+      mint,
+      "key1",
+      "value2",
+      newAuthority
+    );
+    try {
+      await updateField(program, wallet.payer, mint, "key1", "value3");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // TokenMetadataError::IncorrectUpdateAuthority
+      expect(error.message).to.contain("0x35c2b5c0");
+    }
+  });
+});
@@ -0,0 +1,102 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  createInitializeMetadataInstruction,
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+} from "./utils";
+
+describe("mint approval", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+  const configPDA = findConfigPda(program.programId);
+
+  async function initializeMetadata(mint: PublicKey) {
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        createInitializeMetadataInstruction(
+          program.programId,
+          wallet.publicKey,
+          mint,
+          { name: "name", symbol: "symbol", uri: "uri" }
+        )
+      ),
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+  }
+
+  before(async () => {
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    );
+
+    // Require approvals with the wallet as admin
+    await program.methods
+      .initializeConfig(wallet.publicKey, true)
+      .accounts({
+        config: configPDA,
+        program: program.programId,
+        programData,
+        upgradeAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+  });
+
+  after(async () => {
+    // Disable approvals again for the other tests
+    await program.methods
+      .updateConfig(wallet.publicKey, false)
+      .accounts({ config: configPDA, admin: wallet.publicKey })
+      .rpc({ commitment: "confirmed" });
+  });
+
+  it("Initializes metadata for an approved mint", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+
+    await program.methods
+      .approveMint()
+      .accounts({
+        config: configPDA,
+        admin: wallet.publicKey,
+        approval: findApprovalPda(program.programId, mint),
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    await initializeMetadata(mint);
+
+    const metadataPDA = findMetadataPda(program.programId, mint);
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+  });
+
+  it("Rejects metadata for an unapproved mint", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+
+    try {
+      await initializeMetadata(mint);
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      // MetadataError::MintNotApproved
+      expect(error.message).to.contain("0x1774");
+    }
+  });
+});
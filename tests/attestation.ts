@@ -0,0 +1,106 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import { createMintWithPolicy, getProgram, updateField } from "./utils";
+
+describe("attestation", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+  // Signs off-chain only, the wallet submits and pays
+  const attester = Keypair.generate();
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const getAttestation = () =>
+    program.methods
+      .getAttestation()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  // Submit an attestation of the current content signed by the signer
+  const attest = async (signer: Keypair) => {
+    const { hash } = await getAttestation();
+    // Program id, mint, then the hash of the attested content
+    const message = Buffer.concat([
+      program.programId.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from(hash),
+    ]);
+
+    const verifyInstruction = await program.methods
+      .verifyAttestation()
+      .accounts({
+        metadata: metadataPDA,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    return sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        Ed25519Program.createInstructionWithPrivateKey({
+          privateKey: signer.secretKey,
+          message,
+        }),
+        verifyInstruction
+      ),
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+  };
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "TKN", uri: "uri" },
+      { attester: attester.publicKey }
+    ));
+  });
+
+  it("Rejects an attestation from an untrusted key", async () => {
+    try {
+      await attest(Keypair.generate());
+      expect.fail("Attestation should have failed");
+    } catch (error) {
+      // MetadataError::UntrustedAttester
+      expect(error.message).to.contain("0x17a3");
+    }
+    const attestation = await getAttestation();
+    expect(attestation.attested).to.be.false;
+    expect(attestation.attester).to.be.null;
+  });
+
+  it("Stores a valid attestation and the attester", async () => {
+    await attest(attester);
+
+    const attestation = await getAttestation();
+    expect(attestation.attested).to.be.true;
+    expect(attestation.attester.toBase58()).to.equal(
+      attester.publicKey.toBase58()
+    );
+  });
+
+  it("Leaves the attestation stale after a change", async () => {
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+
+    const attestation = await getAttestation();
+    expect(attestation.attested).to.be.false;
+    expect(attestation.attester.toBase58()).to.equal(
+      attester.publicKey.toBase58()
+    );
+  });
+});
@@ -0,0 +1,175 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import { AuthorityCaller } from "../target/types/authority_caller";
+import {
+  createMintWithPolicy,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("authority program", () => {
+  const program = getProgram();
+  const caller = anchor.workspace.AuthorityCaller as Program<AuthorityCaller>;
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const updateThroughCaller = (
+    mint: PublicKey,
+    metadataPDA: PublicKey,
+    value: string
+  ) =>
+    caller.methods
+      .updateField({ field: { key: ["key1"] }, value })
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenMetadataProgram: program.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  it("Accepts an update through the authority program", async () => {
+    const { mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { authorityProgram: caller.programId }
+    );
+
+    await updateThroughCaller(mint, metadataPDA, "value1");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Accepts a relayed update through the authority program", async () => {
+    const { mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { authorityProgram: caller.programId }
+    );
+
+    // Signed by the update authority, as for a direct relay
+    const data = { field: { key: ["key1"] }, value: "value1" };
+    const message = Buffer.concat([
+      program.programId.toBuffer(),
+      mint.toBuffer(),
+      new BN(1).toArrayLike(Buffer, "le", 8),
+      program.coder.types.encode("UpdateFieldData", data),
+    ]);
+    const relayInstruction = await caller.methods
+      .relayUpdateField(data, new BN(1))
+      .accounts({
+        metadata: metadataPDA,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenMetadataProgram: program.programId,
+      })
+      .instruction();
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        Ed25519Program.createInstructionWithPrivateKey({
+          privateKey: wallet.payer.secretKey,
+          message,
+        }),
+        relayInstruction
+      ),
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Rejects a direct update", async () => {
+    const { mint } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { authorityProgram: caller.programId }
+    );
+
+    try {
+      await updateField(program, wallet.payer, mint, "key1", "value1");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::NotInvokedByAuthorityProgram
+      expect(error.message).to.contain("0x178e");
+    }
+  });
+
+  it("Rejects an update through another program", async () => {
+    const { mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { authorityProgram: Keypair.generate().publicKey }
+    );
+
+    try {
+      await updateThroughCaller(mint, metadataPDA, "value1");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("NotInvokedByAuthorityProgram");
+    }
+  });
+
+  it("Rejects direct calls to other gated instructions", async () => {
+    const { mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { authorityProgram: caller.programId }
+    );
+    const accounts = {
+      metadata: metadataPDA,
+      mint,
+      payer: wallet.publicKey,
+      systemProgram: SystemProgram.programId,
+      instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    };
+
+    try {
+      await program.methods
+        .setKeyIfAbsent("key1", "value1")
+        .accounts({ ...accounts, updateAuthority: wallet.publicKey })
+        .rpc();
+      expect.fail("Update should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal(
+        "NotInvokedByAuthorityProgram"
+      );
+    }
+
+    try {
+      await program.methods
+        .updateAuthority(Keypair.generate().publicKey)
+        .accounts({ ...accounts, authority: wallet.publicKey })
+        .rpc();
+      expect.fail("Authority update should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal(
+        "NotInvokedByAuthorityProgram"
+      );
+    }
+  });
+});
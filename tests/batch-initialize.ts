@@ -0,0 +1,152 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  ComputeBudgetProgram,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+} from "./utils";
+
+// Encode items as u16 length prefixed name, symbol and uri
+function encodeCompact(
+  items: { name: string; symbol: string; uri: string }[]
+): Buffer {
+  const fields = items.flatMap(({ name, symbol, uri }) => [name, symbol, uri]);
+  return Buffer.concat(
+    fields.map((field) => {
+      const bytes = Buffer.from(field, "utf-8");
+      const len = Buffer.alloc(2);
+      len.writeUInt16LE(bytes.length);
+      return Buffer.concat([len, bytes]);
+    })
+  );
+}
+
+describe("batch initialize", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  // Short values keep both encodings of 8 items within the transaction size limit
+  const items = [...Array(8).keys()].map((i) => ({
+    name: `B${i}`,
+    symbol: "B",
+    uri: "u",
+  }));
+  const mints: PublicKey[] = [];
+
+  before(async () => {
+    for (const _ of items) {
+      mints.push(await createPointerMint(program, wallet.payer));
+    }
+  });
+
+  const accounts = () => ({
+    mintAuthority: wallet.publicKey,
+    payer: wallet.publicKey,
+    systemProgram: SystemProgram.programId,
+    config: findConfigPda(program.programId),
+  });
+
+  const remainingAccounts = () =>
+    mints.flatMap((mint) => [
+      { pubkey: mint, isSigner: false, isWritable: false },
+      {
+        pubkey: findMetadataPda(program.programId, mint),
+        isSigner: false,
+        isWritable: true,
+      },
+      {
+        pubkey: findApprovalPda(program.programId, mint),
+        isSigner: false,
+        isWritable: false,
+      },
+    ]);
+
+  // Raise the limit so both paths can be measured, even above the default budget
+  const computeLimit = ComputeBudgetProgram.setComputeUnitLimit({
+    units: 1_400_000,
+  });
+
+  const simulateUnits = async (transaction: Transaction) => {
+    const { value } = await connection.simulateTransaction(
+      new Transaction().add(computeLimit, transaction),
+      [wallet.payer]
+    );
+    expect(value.err).to.be.null;
+    return value.unitsConsumed;
+  };
+
+  it("Uses less compute than borsh deserializing the items", async () => {
+    // Both simulated against the same uninitialized mints, so only the parsing differs
+    const compactUnits = await simulateUnits(
+      await program.methods
+        .initializeBatch(encodeCompact(items))
+        .accounts(accounts())
+        .remainingAccounts(remainingAccounts())
+        .transaction()
+    );
+    const borshUnits = await simulateUnits(
+      await program.methods
+        .initializeBatchBorsh(items)
+        .accounts(accounts())
+        .remainingAccounts(remainingAccounts())
+        .transaction()
+    );
+    console.log(
+      `Batch of ${items.length}: ${compactUnits} CU compact, ${borshUnits} CU borsh`
+    );
+
+    expect(compactUnits).to.be.at.most(borshUnits);
+    // Within the default budget of a single instruction
+    expect(compactUnits).to.be.below(200_000);
+  });
+
+  it("Initializes the metadata of every item", async () => {
+    await sendAndConfirmTransaction(
+      connection,
+      await program.methods
+        .initializeBatch(encodeCompact(items))
+        .accounts(accounts())
+        .remainingAccounts(remainingAccounts())
+        .transaction(),
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+
+    for (const [i, mint] of mints.entries()) {
+      const metadata = await fetchMetadata(
+        connection,
+        findMetadataPda(program.programId, mint)
+      );
+      expect(metadata.name).to.equal(items[i].name);
+      expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+      expect(metadata.updateAuthority.toBase58()).to.equal(
+        wallet.publicKey.toBase58()
+      );
+    }
+  });
+
+  it("Rejects a truncated payload", async () => {
+    const payload = encodeCompact(items.slice(0, 1));
+    try {
+      await program.methods
+        .initializeBatch(payload.subarray(0, payload.length - 1))
+        .accounts(accounts())
+        .remainingAccounts(remainingAccounts().slice(0, 3))
+        .rpc();
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("invalid instruction data");
+    }
+  });
+});
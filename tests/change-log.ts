@@ -0,0 +1,73 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createHash } from "crypto";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  findChangeLogPda,
+  getKey,
+  getProgram,
+} from "./utils";
+
+const sha256 = (value: string) => [
+  ...createHash("sha256").update(value).digest(),
+];
+
+describe("change log", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+  let changeLogPDA: PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    changeLogPDA = findChangeLogPda(program.programId, mint);
+
+    await program.methods
+      .initializeChangeLog()
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        changeLog: changeLogPDA,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+  });
+
+  it("Records an update in the change log", async () => {
+    await program.methods
+      .updateField({ field: { key: ["key1"] }, value: "value1" })
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        instructions: null,
+        changeLog: changeLogPDA,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+
+    const changeLog = await program.account.changeLog.fetch(
+      changeLogPDA,
+      "confirmed"
+    );
+    expect(changeLog.mint.toBase58()).to.equal(mint.toBase58());
+    expect(changeLog.count.toNumber()).to.equal(1);
+    expect(changeLog.records[0].fieldHash).to.deep.equal(sha256("key1"));
+    expect(changeLog.records[0].valueHash).to.deep.equal(sha256("value1"));
+  });
+});
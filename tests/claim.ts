@@ -0,0 +1,99 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithPolicy,
+  fetchMetadata,
+  getKey,
+  getProgram,
+} from "./utils";
+
+describe("claim authority", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const owner = Keypair.generate();
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const claimAuthority = (claimant: Keypair) =>
+    program.methods
+      .claimAuthority()
+      .accounts({
+        metadata: metadataPDA,
+        claimant: claimant.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([claimant])
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    // The wallet deploys, the owner claims later
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { claimant: owner.publicKey }
+    ));
+  });
+
+  it("Rejects a claim by someone else", async () => {
+    try {
+      await claimAuthority(Keypair.generate());
+      expect.fail("Claim should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("IncorrectClaimant");
+    }
+  });
+
+  it("Claimant becomes the update authority", async () => {
+    await claimAuthority(owner);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.updateAuthority.toBase58()).to.equal(
+      owner.publicKey.toBase58()
+    );
+    expect(getKey(metadata, "__claimant")).to.be.undefined;
+  });
+
+  it("Rejects a second claim", async () => {
+    try {
+      await claimAuthority(owner);
+      expect.fail("Claim should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("NoPendingClaim");
+    }
+  });
+
+  it("Drops the claim when the metadata is made immutable", async () => {
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { claimant: owner.publicKey }
+    ));
+    await program.methods
+      .updateAuthority(null)
+      .accounts({
+        metadata: metadataPDA,
+        authority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "__claimant")).to.be.undefined;
+
+    try {
+      await claimAuthority(owner);
+      expect.fail("Claim should have failed");
+    } catch (error) {
+      // TokenMetadataError::ImmutableMetadata
+      expect(error.message).to.contain("0x35c2b5c1");
+    }
+  });
+});
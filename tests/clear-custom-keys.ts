@@ -0,0 +1,68 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("clear custom keys", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    for (const key of ["key1", "key2", "key3"]) {
+      await updateField(program, wallet.payer, mint, key, "value");
+    }
+  });
+
+  it("Clears every custom key and keeps the core fields", async () => {
+    const lamportsBefore = await connection.getBalance(
+      metadataPDA,
+      "confirmed"
+    );
+
+    await program.methods
+      .clearCustomKeys()
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("name");
+    expect(metadata.symbol).to.equal("symbol");
+    expect(metadata.uri).to.equal("uri");
+    for (const key of ["key1", "key2", "key3"]) {
+      expect(getKey(metadata, key)).to.be.undefined;
+    }
+    // Reserved keys are kept
+    expect(getKey(metadata, "__integrity")).to.not.be.undefined;
+
+    // The account shrank and the excess rent was refunded
+    const { data, lamports } = await connection.getAccountInfo(
+      metadataPDA,
+      "confirmed"
+    );
+    expect(lamports).to.be.below(lamportsBefore);
+    expect(lamports).to.equal(
+      await connection.getMinimumBalanceForRentExemption(data.length)
+    );
+  });
+});
@@ -0,0 +1,84 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createHash, randomBytes } from "crypto";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+} from "./utils";
+
+// sha256(value || salt), as checked by reveal_field
+const commitment = (value: string, salt: Buffer) => [
+  ...createHash("sha256").update(value).update(salt).digest(),
+];
+
+describe("commit reveal", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const accounts = () => ({
+    metadata: metadataPDA,
+    updateAuthority: wallet.publicKey,
+    mint,
+    payer: wallet.publicKey,
+    systemProgram: SystemProgram.programId,
+  });
+
+  const commit = (key: string, value: string, salt: Buffer) =>
+    program.methods
+      .commitField({ key: [key] }, commitment(value, salt))
+      .accounts(accounts())
+      .rpc({ commitment: "confirmed" });
+
+  const reveal = (key: string, value: string, salt: Buffer) =>
+    program.methods
+      .revealField({ key: [key] }, value, [...salt])
+      .accounts(accounts())
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Applies a reveal matching the commitment", async () => {
+    const salt = randomBytes(32);
+    await commit("rarity", "legendary", salt);
+    expect(getKey(await fetchMetadata(connection, metadataPDA), "rarity")).to.be
+      .undefined;
+
+    await reveal("rarity", "legendary", salt);
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "rarity")).to.equal("legendary");
+    // The commitment is consumed by the reveal
+    expect(getKey(metadata, "__commit.key.rarity")).to.be.undefined;
+  });
+
+  it("Rejects a reveal with a wrong value or salt", async () => {
+    const salt = randomBytes(32);
+    await commit("color", "gold", salt);
+
+    for (const [value, revealSalt] of [
+      ["silver", salt],
+      ["gold", randomBytes(32)],
+    ] as [string, Buffer][]) {
+      try {
+        await reveal("color", value, revealSalt);
+        expect.fail("Reveal should have failed");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("CommitmentMismatch");
+      }
+    }
+    expect(getKey(await fetchMetadata(connection, metadataPDA), "color")).to.be
+      .undefined;
+  });
+});
@@ -0,0 +1,48 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  simulateReturnData,
+} from "./utils";
+
+// Decode u16 length prefixed strings
+function decodeCompact(data: Buffer): string[] {
+  const fields = [];
+  let offset = 0;
+  while (offset < data.length) {
+    const len = data.readUInt16LE(offset);
+    offset += 2;
+    fields.push(data.subarray(offset, offset + len).toString("utf-8"));
+    offset += len;
+  }
+  return fields;
+}
+
+describe("emit compact", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Returns core fields matching a full read", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "Compact Token", symbol: "CMPT", uri: "https://example.com" }
+    );
+
+    const transaction = await program.methods
+      .emitCompact()
+      .accounts({ metadata: metadataPDA, mint })
+      .transaction();
+    const data = await simulateReturnData(program, transaction, wallet.payer);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(decodeCompact(data)).to.deep.equal([
+      metadata.name,
+      metadata.symbol,
+      metadata.uri,
+    ]);
+  });
+});
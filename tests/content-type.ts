@@ -0,0 +1,51 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("uri content type", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setUriContentType = (mime: string) =>
+    program.methods
+      .setUriContentType(mime)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "https://example.com/image.png" }
+    ));
+  });
+
+  it("Sets the content type of the uri", async () => {
+    await setUriContentType("image/png");
+
+    const contentType = await program.methods
+      .getUriContentType()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(contentType).to.equal("image/png");
+  });
+
+  it("Rejects a malformed MIME type", async () => {
+    try {
+      await setUriContentType("notamime");
+      expect.fail("Set content type should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidMimeType");
+    }
+  });
+});
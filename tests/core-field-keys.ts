@@ -0,0 +1,48 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Field } from "@solana/spl-token-metadata";
+import { PublicKey } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("core field keys", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Rejects custom keys named like a core field", async () => {
+    for (const key of ["name", "symbol", "uri"]) {
+      try {
+        await updateField(program, wallet.payer, mint, key, "shadow");
+        expect.fail("Update should have failed");
+      } catch (error) {
+        // MetadataError::KeyCollidesWithCoreField
+        expect(error.message).to.contain("0x17a1");
+      }
+    }
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "name")).to.be.undefined;
+  });
+
+  it("Updates the core field through its own variant", async () => {
+    await updateField(program, wallet.payer, mint, Field.Name, "new name");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("new name");
+  });
+});
@@ -0,0 +1,179 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  createTestHelperInstruction,
+  getProgram,
+  updateField,
+} from "./utils";
+
+// Write the bytes at the offset of the metadata account, as the mint authority
+const corruptForTest = (
+  program: ReturnType<typeof getProgram>,
+  mintAuthority: Keypair,
+  mint: PublicKey,
+  metadataPDA: PublicKey,
+  offset: number,
+  bytes: Buffer
+) => {
+  // u32 offset, then the borsh Vec<u8>
+  const args = Buffer.alloc(8);
+  args.writeUInt32LE(offset, 0);
+  args.writeUInt32LE(bytes.length, 4);
+  const instruction = createTestHelperInstruction(
+    program.programId,
+    "corrupt_for_test",
+    [
+      { pubkey: metadataPDA, isSigner: false, isWritable: true },
+      { pubkey: mint, isSigner: false, isWritable: false },
+      { pubkey: mintAuthority.publicKey, isSigner: true, isWritable: false },
+    ],
+    Buffer.concat([args, bytes])
+  );
+  return sendAndConfirmTransaction(
+    program.provider.connection,
+    new Transaction().add(instruction),
+    [mintAuthority],
+    { commitment: "confirmed" }
+  );
+};
+
+// corrupt_for_test only writes in a build with
+// `anchor build -- --features test-helpers`, run with TEST_HELPERS=1 against it
+describe("corrupt for test", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const corrupt = (offset: number, bytes: Buffer) =>
+    corruptForTest(program, wallet.payer, mint, metadataPDA, offset, bytes);
+
+  const getMetadata = () =>
+    program.methods
+      .getMetadata()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async function () {
+    if (process.env.TEST_HELPERS !== "1") {
+      this.skip();
+    }
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Rejects a write past the end of the account", async () => {
+    const { data } = await connection.getAccountInfo(metadataPDA, "confirmed");
+    try {
+      await corrupt(data.length - 1, Buffer.from([0, 0]));
+      expect.fail("Corrupt should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("account data too small");
+    }
+  });
+
+  const repairSize = (updateAuthority: Keypair = wallet.payer) =>
+    program.methods
+      .repairSize()
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: updateAuthority.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(updateAuthority === wallet.payer ? [] : [updateAuthority])
+      .rpc({ commitment: "confirmed" });
+
+  it("Repairs a corrupt TLV length", async () => {
+    // The TLV length follows the 8 byte type discriminator
+    const { data } = await connection.getAccountInfo(metadataPDA, "confirmed");
+    const length = data.readUInt32LE(8);
+
+    await corrupt(8, Buffer.from([0xff, 0xff, 0xff, 0xff]));
+    for (const read of [
+      getMetadata,
+      () => updateField(program, wallet.payer, mint, "key1", "value1"),
+    ]) {
+      try {
+        await read();
+        expect.fail("Read should have failed");
+      } catch (error) {
+        expect(error.message).to.not.contain("Read should have failed");
+      }
+    }
+
+    // Only the update authority can repair
+    try {
+      await repairSize(Keypair.generate());
+      expect.fail("Repair should have failed");
+    } catch (error) {
+      // TokenMetadataError::IncorrectUpdateAuthority
+      expect(error.message).to.contain("0x35c2b5c0");
+    }
+
+    await repairSize();
+    const repaired = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(repaired.data.readUInt32LE(8)).to.equal(length);
+    expect((await getMetadata()).name).to.equal("name");
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+  });
+
+  it("Leaves a healthy account unchanged", async () => {
+    const before = await connection.getAccountInfo(metadataPDA, "confirmed");
+    await repairSize();
+    const after = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(after.data.equals(before.data)).to.be.true;
+    expect(after.lamports).to.equal(before.lamports);
+  });
+});
+
+describe("corrupt for test without test helpers", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  before(function () {
+    if (process.env.TEST_HELPERS === "1") {
+      this.skip();
+    }
+  });
+
+  it("Doesn't know the instruction in a production build", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    try {
+      await corruptForTest(
+        program,
+        wallet.payer,
+        mint,
+        metadataPDA,
+        8,
+        Buffer.from([0xff])
+      );
+      expect.fail("Corrupt should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("invalid instruction data");
+    }
+    // Not in the IDL either
+    expect(program.idl.instructions.map(({ name }) => name)).to.not.include(
+      "corruptForTest"
+    );
+  });
+});
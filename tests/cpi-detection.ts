@@ -0,0 +1,96 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  ComputeBudgetProgram,
+  Keypair,
+  PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+} from "@solana/web3.js";
+import { AuthorityCaller } from "../target/types/authority_caller";
+import {
+  createTestHelperInstruction,
+  getProgram,
+  simulateReturnData,
+} from "./utils";
+
+// Decode a borsh Option<Pubkey>
+function decodeCaller(data: Buffer): PublicKey | null {
+  return data[0] === 1 ? new PublicKey(data.subarray(1, 33)) : null;
+}
+
+// get_cpi_caller only answers in a build with
+// `anchor build -- --features test-helpers`, run with TEST_HELPERS=1 against it
+describe("cpi detection", () => {
+  const program = getProgram();
+  const caller = anchor.workspace.AuthorityCaller as Program<AuthorityCaller>;
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  before(function () {
+    if (process.env.TEST_HELPERS !== "1") {
+      this.skip();
+    }
+  });
+
+  const direct = async (instructions = SYSVAR_INSTRUCTIONS_PUBKEY) =>
+    createTestHelperInstruction(program.programId, "get_cpi_caller", [
+      { pubkey: instructions, isSigner: false, isWritable: false },
+    ]);
+
+  const throughCaller = () =>
+    caller.methods
+      .forwardGetCpiCaller()
+      .accounts({
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenMetadataProgram: program.programId,
+      })
+      .instruction();
+
+  // Another top level instruction first, so the current index isn't 0
+  const computeLimit = ComputeBudgetProgram.setComputeUnitLimit({
+    units: 200_000,
+  });
+
+  const simulateCaller = async (transaction: Transaction) =>
+    decodeCaller(await simulateReturnData(program, transaction, wallet.payer));
+
+  it("Reports no caller for a direct call", async () => {
+    expect(await simulateCaller(new Transaction().add(await direct()))).to.be
+      .null;
+  });
+
+  it("Reports no caller for a direct call after another instruction", async () => {
+    const transaction = new Transaction().add(computeLimit, await direct());
+    expect(await simulateCaller(transaction)).to.be.null;
+  });
+
+  it("Reports the calling program for a CPI", async () => {
+    const transaction = new Transaction().add(await throughCaller());
+    expect((await simulateCaller(transaction)).toBase58()).to.equal(
+      caller.programId.toBase58()
+    );
+  });
+
+  it("Reports the calling program for a CPI after another instruction", async () => {
+    const transaction = new Transaction().add(
+      computeLimit,
+      await throughCaller()
+    );
+    expect((await simulateCaller(transaction)).toBase58()).to.equal(
+      caller.programId.toBase58()
+    );
+  });
+
+  it("Rejects an account other than the instructions sysvar", async () => {
+    const transaction = new Transaction().add(
+      await direct(Keypair.generate().publicKey)
+    );
+    try {
+      await simulateCaller(transaction);
+      expect.fail("Read should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("UnsupportedSysvar");
+    }
+  });
+});
@@ -0,0 +1,91 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("delegate", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const delegate = Keypair.generate();
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  // Lamports held above the rent exempt minimum
+  const heldLamports = async () => {
+    const account = await connection.getAccountInfo(metadataPDA, "confirmed");
+    const rent = await connection.getMinimumBalanceForRentExemption(
+      account.data.length
+    );
+    return account.lamports - rent;
+  };
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await program.methods
+      .setDelegate(delegate.publicKey, true)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+  });
+
+  it("Delegate updates a field", async () => {
+    await updateField(
+      program,
+      wallet.payer,
+      mint,
+      "key1",
+      "a long value",
+      delegate
+    );
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.additionalMetadata).to.deep.include(["key1", "a long value"]);
+    expect(await heldLamports()).to.equal(0);
+  });
+
+  it("Holds the refund of a delegate shrink", async () => {
+    await updateField(program, wallet.payer, mint, "key1", "short", delegate);
+    expect(await heldLamports()).to.be.greaterThan(0);
+  });
+
+  it("Authority claims the held refund", async () => {
+    const held = await heldLamports();
+    const balanceBefore = await connection.getBalance(
+      wallet.publicKey,
+      "confirmed"
+    );
+
+    await program.methods
+      .claimRefund()
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    expect(await heldLamports()).to.equal(0);
+    // Less the transaction fee
+    const balanceAfter = await connection.getBalance(
+      wallet.publicKey,
+      "confirmed"
+    );
+    expect(balanceAfter).to.be.greaterThan(balanceBefore + held - 10000);
+  });
+});
@@ -0,0 +1,75 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("deprecate key", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const deprecateKey = (key: string) =>
+    program.methods
+      .deprecateKey(key)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const readKeys = async (hideDeprecated: boolean) =>
+    (
+      await program.methods
+        .getMetadataFiltered(hideDeprecated)
+        .accounts({ metadata: metadataPDA, mint })
+        .view()
+    ).additionalMetadata.map(({ key }) => key);
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await updateField(program, wallet.payer, mint, "old", "value1");
+    await updateField(program, wallet.payer, mint, "current", "value2");
+  });
+
+  it("Replaces the value with a tombstone and keeps the key", async () => {
+    await deprecateKey("old");
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "old")).to.equal("__deprecated");
+    expect(getKey(metadata, "current")).to.equal("value2");
+  });
+
+  it("Hides deprecated keys on read only if requested", async () => {
+    expect(await readKeys(false)).to.include.members(["old", "current"]);
+
+    const visible = await readKeys(true);
+    expect(visible).to.include("current");
+    expect(visible).to.not.include("old");
+  });
+
+  it("Rejects deprecating a missing key", async () => {
+    try {
+      await deprecateKey("missing");
+      expect.fail("Deprecate should have failed");
+    } catch (error) {
+      // TokenMetadataError::KeyNotFound
+      expect(error.message).to.contain("0x35c2b5c2");
+    }
+  });
+});
@@ -0,0 +1,44 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createMintWithMetadata, getProgram, updateField } from "./utils";
+
+describe("diff metadata", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  it("Returns the fields and keys that differ", async () => {
+    const a = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "AAA",
+      uri: "uri",
+    });
+    const b = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "BBB",
+      uri: "uri",
+    });
+
+    // Same value, different values, and keys only on one side
+    for (const [key, valueA, valueB] of [
+      ["same", "value", "value"],
+      ["changed", "old", "new"],
+    ]) {
+      await updateField(program, wallet.payer, a.mint, key, valueA);
+      await updateField(program, wallet.payer, b.mint, key, valueB);
+    }
+    await updateField(program, wallet.payer, a.mint, "onlyA", "value");
+    await updateField(program, wallet.payer, b.mint, "onlyB", "value");
+
+    const diff = await program.methods
+      .diffMetadata()
+      .accounts({ metadataA: a.metadataPDA, metadataB: b.metadataPDA })
+      .view();
+    expect(diff).to.deep.equal({
+      name: false,
+      symbol: true,
+      uri: false,
+      keys: ["changed", "onlyA", "onlyB"],
+      truncated: false,
+    });
+  });
+});
@@ -0,0 +1,54 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("display", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setDisplay = (decimals: number, prefix: string, suffix: string) =>
+    program.methods
+      .setDisplay(decimals, prefix, suffix)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    // Mint has 9 decimals
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Sets display config", async () => {
+    await setDisplay(6, "$", " USD");
+
+    const display = await program.methods
+      .getDisplay()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(display.decimalsOverride).to.equal(6);
+    expect(display.prefix).to.equal("$");
+    expect(display.suffix).to.equal(" USD");
+  });
+
+  it("Rejects decimals above the mint decimals", async () => {
+    try {
+      await setDisplay(10, "$", " USD");
+      expect.fail("Set display should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidDisplayDecimals");
+    }
+  });
+});
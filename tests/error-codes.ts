@@ -0,0 +1,25 @@
+import { expect } from "chai";
+import { getProgram } from "./utils";
+
+// First code of spl-token-metadata-interface's TokenMetadataError, hashed from
+// "spl_token_metadata_interface", followed by IncorrectAccount through KeyNotFound
+const SPL_TOKEN_METADATA_ERROR_START = 0x35c2b5bd;
+const SPL_TOKEN_METADATA_ERROR_COUNT = 6;
+
+describe("error codes", () => {
+  const program = getProgram();
+  const codes = program.idl.errors.map(({ code }) => code);
+
+  it("Numbers program errors from the Anchor custom error offset", () => {
+    expect(Math.min(...codes)).to.equal(6000);
+    // Contiguous, so no code is skipped or reused
+    expect(codes).to.deep.equal(codes.map((_, i) => 6000 + i));
+  });
+
+  it("Doesn't overlap the SPL token metadata interface errors", () => {
+    expect(Math.max(...codes)).to.be.below(SPL_TOKEN_METADATA_ERROR_START);
+    for (let i = 0; i < SPL_TOKEN_METADATA_ERROR_COUNT; i++) {
+      expect(codes).to.not.include(SPL_TOKEN_METADATA_ERROR_START + i);
+    }
+  });
+});
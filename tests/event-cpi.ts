@@ -0,0 +1,113 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createPointerMint,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+} from "./utils";
+
+// Discriminator of the self-CPI instruction carrying an event
+const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
+// Only built with `anchor build -p token_metadata -- --features event-cpi`,
+// run with EVENT_CPI=1 against such a build
+describe("event cpi", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  // The IDL doesn't list the accounts added by the feature, they follow the declared ones
+  const eventAccounts = [
+    {
+      pubkey: PublicKey.findProgramAddressSync(
+        [Buffer.from("__event_authority")],
+        program.programId
+      )[0],
+      isSigner: false,
+      isWritable: false,
+    },
+    { pubkey: program.programId, isSigner: false, isWritable: false },
+  ];
+
+  // Decode the events emitted through self-CPIs in the transaction
+  const cpiEvents = async (signature: string) => {
+    const transaction = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const accountKeys = transaction.transaction.message.getAccountKeys();
+    return transaction.meta.innerInstructions
+      .flatMap(({ instructions }) => instructions)
+      .filter(({ programIdIndex }) =>
+        accountKeys.get(programIdIndex).equals(program.programId)
+      )
+      .map(({ data }) => Buffer.from(anchor.utils.bytes.bs58.decode(data)))
+      .filter((data) => data.subarray(0, 8).equals(EVENT_IX_TAG))
+      .map((data) =>
+        program.coder.events.decode(
+          anchor.utils.bytes.base64.encode(data.subarray(8))
+        )
+      );
+  };
+
+  let mint: PublicKey;
+
+  before(async function () {
+    if (process.env.EVENT_CPI !== "1") {
+      this.skip();
+    }
+    mint = await createPointerMint(program, wallet.payer);
+  });
+
+  it("Emits the initialize event through a self-CPI", async () => {
+    const signature = await program.methods
+      .initialize({ name: "name", symbol: "symbol", uri: "uri" })
+      .accounts({
+        metadata: findMetadataPda(program.programId, mint),
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+        registry: null,
+        registryPage: null,
+        symbolClaim: null,
+      })
+      .remainingAccounts(eventAccounts)
+      .rpc({ commitment: "confirmed" });
+
+    const [event] = await cpiEvents(signature);
+    expect(event.name).to.equal("MetadataInitialized");
+    expect(event.data.mint.toBase58()).to.equal(mint.toBase58());
+    expect(event.data.updateAuthority.toBase58()).to.equal(
+      wallet.publicKey.toBase58()
+    );
+  });
+
+  it("Emits the update event through a self-CPI", async () => {
+    const signature = await program.methods
+      .updateField({ field: { key: ["key1"] }, value: "value1" })
+      .accounts({
+        metadata: findMetadataPda(program.programId, mint),
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        instructions: null,
+        changeLog: null,
+      })
+      .remainingAccounts(eventAccounts)
+      .rpc({ commitment: "confirmed" });
+
+    const [event] = await cpiEvents(signature);
+    expect(event.name).to.equal("FieldUpdated");
+    expect(event.data.mint.toBase58()).to.equal(mint.toBase58());
+    expect(event.data.field.key[0]).to.equal("key1");
+    expect(event.data.value).to.equal("value1");
+  });
+});
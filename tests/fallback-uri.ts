@@ -0,0 +1,59 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("fallback uri", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setFallbackUri = (uri: string) =>
+    program.methods
+      .setFallbackUri(uri)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const getUris = () =>
+    program.methods.getUris().accounts({ metadata: metadataPDA, mint }).view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "https://example.com/token.json" }
+    ));
+  });
+
+  it("Returns no fallback until one is set", async () => {
+    expect(await getUris()).to.deep.equal({
+      uri: "https://example.com/token.json",
+      fallbackUri: null,
+    });
+  });
+
+  it("Returns both URIs after setting a fallback", async () => {
+    await setFallbackUri("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi");
+    expect(await getUris()).to.deep.equal({
+      uri: "https://example.com/token.json",
+      fallbackUri: "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+    });
+  });
+
+  it("Rejects a fallback without a scheme", async () => {
+    try {
+      await setFallbackUri("example.com/token.json");
+      expect.fail("Set should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidUri");
+    }
+  });
+});
@@ -0,0 +1,62 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("filter by symbol", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  const symbols = ["AAA", "BBB", "AAA", "CCC", "AAA"];
+  const tokens: { mint: PublicKey; metadataPDA: PublicKey }[] = [];
+
+  before(async () => {
+    for (const symbol of symbols) {
+      tokens.push(
+        await createMintWithMetadata(program, wallet.payer, {
+          name: "name",
+          symbol,
+          uri: "uri",
+        })
+      );
+    }
+  });
+
+  it("Returns the mints matching the symbol", async () => {
+    const mints: PublicKey[] = await program.methods
+      .filterBySymbol("AAA")
+      .accounts({ tokenMetadataProgram: program.programId })
+      .remainingAccounts(
+        tokens.map(({ metadataPDA }) => ({
+          pubkey: metadataPDA,
+          isSigner: false,
+          isWritable: false,
+        }))
+      )
+      .view();
+
+    const expected = tokens.filter((_, i) => symbols[i] === "AAA");
+    expect(mints.map((mint) => mint.toBase58())).to.deep.equal(
+      expected.map(({ mint }) => mint.toBase58())
+    );
+  });
+
+  it("Rejects a metadata account in place of the program", async () => {
+    // The first account is the program, it can't be skipped for a metadata account
+    const [first, ...rest] = tokens.map(({ metadataPDA }) => ({
+      pubkey: metadataPDA,
+      isSigner: false,
+      isWritable: false,
+    }));
+    try {
+      await program.methods
+        .filterBySymbol("AAA")
+        .accounts({ tokenMetadataProgram: first.pubkey })
+        .remainingAccounts(rest)
+        .view();
+      expect.fail("Filter should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("InvalidProgramId");
+    }
+  });
+});
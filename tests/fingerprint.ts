@@ -0,0 +1,95 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { Field } from "@solana/spl-token-metadata";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("fingerprint", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const getFingerprint = async (): Promise<string> =>
+    Buffer.from(
+      await program.methods
+        .getFingerprint()
+        .accounts({ metadata: metadataPDA, mint })
+        .view()
+    ).toString("hex");
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Returns a stable 8 byte fingerprint", async () => {
+    const fingerprint = await getFingerprint();
+    expect(fingerprint).to.have.lengthOf(16);
+    expect(await getFingerprint()).to.equal(fingerprint);
+  });
+
+  it("Is the prefix of the integrity hash", async () => {
+    const metadata = await fetchMetadata(
+      program.provider.connection,
+      metadataPDA
+    );
+    const integrity = anchor.utils.bytes.bs58.decode(
+      getKey(metadata, "__integrity")
+    );
+    expect(await getFingerprint()).to.equal(
+      Buffer.from(integrity.subarray(0, 8)).toString("hex")
+    );
+  });
+
+  it("Changes on every field change", async () => {
+    const seen = new Set([await getFingerprint()]);
+    const changes: [Field | string, string][] = [
+      [Field.Name, "new name"],
+      [Field.Symbol, "NEW"],
+      [Field.Uri, "new uri"],
+      ["key1", "value1"],
+      ["key1", "value2"],
+      ["key2", "value2"],
+    ];
+    for (const [field, value] of changes) {
+      await updateField(program, wallet.payer, mint, field, value);
+      const fingerprint = await getFingerprint();
+      expect(seen.has(fingerprint)).to.be.false;
+      seen.add(fingerprint);
+    }
+  });
+
+  it("Stays the same when an update writes the current value", async () => {
+    await updateField(program, wallet.payer, mint, "key1", "same");
+    const fingerprint = await getFingerprint();
+
+    await updateField(program, wallet.payer, mint, "key1", "same");
+    expect(await getFingerprint()).to.equal(fingerprint);
+  });
+
+  it("Changes when the update authority changes", async () => {
+    const fingerprint = await getFingerprint();
+    await program.methods
+      .updateAuthority(Keypair.generate().publicKey)
+      .accounts({
+        metadata: metadataPDA,
+        authority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+    expect(await getFingerprint()).to.not.equal(fingerprint);
+  });
+});
@@ -0,0 +1,95 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+} from "./utils";
+
+describe("flags", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setFlag = (
+    flag: object,
+    value: boolean,
+    updateAuthority: Keypair = wallet.payer
+  ) =>
+    program.methods
+      .setFlag(flag, value)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: updateAuthority.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([updateAuthority])
+      .rpc({ commitment: "confirmed" });
+
+  const getFlags = () =>
+    program.methods
+      .getFlags()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Returns no flags before any is set", async () => {
+    expect(await getFlags()).to.equal(0);
+  });
+
+  it("Sets individual flags into the combined mask", async () => {
+    await setFlag({ verified: {} }, true);
+    expect(await getFlags()).to.equal(0b0001);
+
+    await setFlag({ revealed: {} }, true);
+    await setFlag({ attested: {} }, true);
+    expect(await getFlags()).to.equal(0b1101);
+
+    // Setting a flag again leaves the mask unchanged
+    await setFlag({ revealed: {} }, true);
+    expect(await getFlags()).to.equal(0b1101);
+
+    // Stored as a single reserved key
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "__flags")).to.equal("13");
+  });
+
+  it("Clears individual flags, removing the key once none are set", async () => {
+    await setFlag({ revealed: {} }, false);
+    expect(await getFlags()).to.equal(0b1001);
+
+    // Clearing a flag that isn't set leaves the mask unchanged
+    await setFlag({ frozen: {} }, false);
+    expect(await getFlags()).to.equal(0b1001);
+
+    await setFlag({ verified: {} }, false);
+    await setFlag({ attested: {} }, false);
+    expect(await getFlags()).to.equal(0);
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "__flags")).to.be.undefined;
+  });
+
+  it("Rejects setting a flag without the update authority", async () => {
+    try {
+      await setFlag({ verified: {} }, true, Keypair.generate());
+      expect.fail("Set flag should have failed");
+    } catch (error) {
+      // TokenMetadataError::IncorrectUpdateAuthority
+      expect(error.message).to.contain("0x35c2b5c0");
+    }
+  });
+});
@@ -0,0 +1,116 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithPolicy,
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+} from "./utils";
+
+describe("funded authority", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const policy = { requireFundedAuthority: true };
+
+  // New keypair holding the rent-exempt minimum of an empty account
+  const fundedKeypair = async () => {
+    const keypair = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(
+        keypair.publicKey,
+        await connection.getMinimumBalanceForRentExemption(0)
+      ),
+      "confirmed"
+    );
+    return keypair;
+  };
+
+  const initialize = async (updateAuthority: PublicKey) => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const metadataPDA = findMetadataPda(program.programId, mint);
+    await program.methods
+      .initializeWithPolicy({ name: "name", symbol: "TKN", uri: "uri" }, policy)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+    return metadataPDA;
+  };
+
+  it("Rejects an unfunded update authority at initialize", async () => {
+    try {
+      await initialize(Keypair.generate().publicKey);
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("AuthorityUnfunded");
+    }
+  });
+
+  it("Accepts a funded update authority at initialize", async () => {
+    const authority = await fundedKeypair();
+    const metadataPDA = await initialize(authority.publicKey);
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.updateAuthority.toBase58()).to.equal(
+      authority.publicKey.toBase58()
+    );
+  });
+
+  describe("update authority", () => {
+    let mint: PublicKey;
+    let metadataPDA: PublicKey;
+
+    const updateAuthority = (newAuthority: PublicKey) =>
+      program.methods
+        .updateAuthority(newAuthority)
+        .accounts({
+          metadata: metadataPDA,
+          authority: wallet.publicKey,
+          newAuthority,
+          mint,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      ({ mint, metadataPDA } = await createMintWithPolicy(
+        program,
+        wallet.payer,
+        { name: "name", symbol: "TKN", uri: "uri" },
+        policy
+      ));
+    });
+
+    it("Rejects an unfunded new authority", async () => {
+      try {
+        await updateAuthority(Keypair.generate().publicKey);
+        expect.fail("Update authority should have failed");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("AuthorityUnfunded");
+      }
+    });
+
+    it("Accepts a funded new authority", async () => {
+      const authority = await fundedKeypair();
+      await updateAuthority(authority.publicKey);
+
+      const metadata = await fetchMetadata(connection, metadataPDA);
+      expect(metadata.updateAuthority.toBase58()).to.equal(
+        authority.publicKey.toBase58()
+      );
+    });
+  });
+});
@@ -0,0 +1,116 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Field } from "@solana/spl-token-metadata";
+import { PublicKey } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+// Small seeded generator, so a failing sequence can be replayed with FUZZ_SEED
+function mulberry32(seed: number) {
+  return () => {
+    seed = (seed + 0x6d2b79f5) | 0;
+    let t = Math.imul(seed ^ (seed >>> 15), 1 | seed);
+    t = (t + Math.imul(t ^ (t >>> 7), 61 | t)) ^ t;
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+describe("fuzz update field", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const seed = Number(process.env.FUZZ_SEED ?? 1);
+  const steps = Number(process.env.FUZZ_STEPS ?? 40);
+  const random = mulberry32(seed);
+
+  // Uri and custom keys, up to MAX_VALUE_LEN so the account grows and shrinks
+  const fields: (Field | string)[] = [Field.Uri, "key0", "key1", "key2"];
+  const randomValue = () => {
+    // Favor short values and equal lengths, which take the in place path
+    const length = [0, 1, 10, 10, 64, 512][Math.floor(random() * 6)];
+    return [...Array(length)]
+      .map(() => "abcdefghijklmnopqrstuvwxyz"[Math.floor(random() * 26)])
+      .join("");
+  };
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Keeps the account reconciled through random updates", async () => {
+    console.log(`FUZZ_SEED=${seed} FUZZ_STEPS=${steps}`);
+    const expected = new Map<Field | string, string>([[Field.Uri, "uri"]]);
+
+    for (let step = 0; step < steps; step++) {
+      const field = fields[Math.floor(random() * fields.length)];
+      const value = randomValue();
+      const name = field === Field.Uri ? "uri" : field;
+      const context = `step ${step}: ${name} = ${value.length} bytes`;
+
+      const payerBefore = await connection.getBalance(
+        wallet.publicKey,
+        "confirmed"
+      );
+      const accountBefore = await connection.getBalance(
+        metadataPDA,
+        "confirmed"
+      );
+      const signature = await updateField(
+        program,
+        wallet.payer,
+        mint,
+        field,
+        value
+      );
+      expected.set(field, value);
+
+      const { data, lamports } = await connection.getAccountInfo(
+        metadataPDA,
+        "confirmed"
+      );
+      const payerAfter = await connection.getBalance(
+        wallet.publicKey,
+        "confirmed"
+      );
+      const { meta } = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+
+      // Exactly rent exempt, no lamports left behind or missing
+      expect(lamports, context).to.equal(
+        await connection.getMinimumBalanceForRentExemption(data.length)
+      );
+      // The account is sized to the packed value
+      expect(data.length, context).to.equal(12 + data.readUInt32LE(8));
+      // Lamports only move between the payer and the account, besides the fee
+      expect(
+        payerAfter - payerBefore + (lamports - accountBefore),
+        context
+      ).to.equal(-meta.fee);
+
+      // The packed value round-trips
+      const metadata = await fetchMetadata(connection, metadataPDA);
+      expect(metadata.name, context).to.equal("name");
+      expect(metadata.symbol, context).to.equal("symbol");
+      for (const [key, value] of expected) {
+        const actual =
+          key === Field.Uri ? metadata.uri : getKey(metadata, key as string);
+        expect(actual, context).to.equal(value);
+      }
+    }
+  });
+});
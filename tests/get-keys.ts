@@ -0,0 +1,72 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  createMintWithMetadata,
+  getProgram,
+  simulateReturnData,
+  updateField,
+} from "./utils";
+
+// u16 length prefix of a missing key
+const MISSING_KEY_SENTINEL = 0xffff;
+
+// Decode u16 length prefixed values, null for the sentinel
+function decodeValues(data: Buffer): (string | null)[] {
+  const values = [];
+  let offset = 0;
+  while (offset < data.length) {
+    const len = data.readUInt16LE(offset);
+    offset += 2;
+    if (len === MISSING_KEY_SENTINEL) {
+      values.push(null);
+      continue;
+    }
+    values.push(data.subarray(offset, offset + len).toString("utf-8"));
+    offset += len;
+  }
+  return values;
+}
+
+describe("get keys", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  it("Returns values for present keys and the sentinel for missing keys", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    await updateField(program, wallet.payer, mint, "color", "blue");
+    await updateField(program, wallet.payer, mint, "level", "3");
+
+    const transaction = await program.methods
+      .getKeys(["level", "size", "color"])
+      .accounts({ metadata: metadataPDA, mint })
+      .transaction();
+    const data = await simulateReturnData(program, transaction, wallet.payer);
+
+    // In request order, not the stored order
+    expect(decodeValues(data)).to.deep.equal(["3", null, "blue"]);
+  });
+
+  it("Rejects more keys than the limit", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    const transaction = await program.methods
+      .getKeys([...Array(17).keys()].map((i) => `key${i}`))
+      .accounts({ metadata: metadataPDA, mint })
+      .transaction();
+    try {
+      await simulateReturnData(program, transaction, wallet.payer);
+      expect.fail("Read should have failed");
+    } catch (error) {
+      // MetadataError::TooManyKeys
+      expect(error.message).to.contain('"Custom":6031');
+    }
+  });
+});
@@ -0,0 +1,72 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  simulateReturnData,
+  updateField,
+} from "./utils";
+
+describe("get metadata", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Returns additional metadata sorted by key", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    for (const key of ["zeta", "alpha", "mu"]) {
+      await updateField(program, wallet.payer, mint, key, key);
+    }
+
+    const stored = (await fetchMetadata(connection, metadataPDA))
+      .additionalMetadata.map(([key]) => key);
+    const sorted = [...stored].sort();
+    expect(stored).to.not.deep.equal(sorted);
+
+    const metadata = await program.methods
+      .getMetadata()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(metadata.name).to.equal("name");
+    expect(metadata.additionalMetadata.map((entry) => entry.key)).to.deep.equal(
+      sorted
+    );
+  });
+
+  it("Decodes the return data with the IDL type", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+
+    expect(program.idl.types.map(({ name }) => name)).to.include(
+      "MetadataView"
+    );
+    const transaction = await program.methods
+      .getMetadata()
+      .accounts({ metadata: metadataPDA, mint })
+      .transaction();
+    const metadata = program.coder.types.decode(
+      "MetadataView",
+      await simulateReturnData(program, transaction, wallet.payer)
+    );
+
+    expect(metadata.updateAuthority.toBase58()).to.equal(
+      wallet.publicKey.toBase58()
+    );
+    expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+    expect(metadata.name).to.equal("name");
+    expect(metadata.symbol).to.equal("symbol");
+    expect(metadata.uri).to.equal("uri");
+    expect(
+      metadata.additionalMetadata.find(({ key }) => key === "key1").value
+    ).to.equal("value1");
+  });
+});
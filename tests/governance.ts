@@ -0,0 +1,220 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  createMintWithPolicy,
+  fetchMetadata,
+  getProgram,
+} from "./utils";
+
+describe("governance", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const members = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+  let governancePDA: PublicKey;
+
+  const findProposalPda = (nonce: number) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("proposal"),
+        mint.toBuffer(),
+        new BN(nonce).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+  const propose = (nonce: number, value: string) =>
+    program.methods
+      .proposeUpdate(new BN(nonce), { field: { name: {} }, value })
+      .accounts({
+        governance: governancePDA,
+        proposal: findProposalPda(nonce),
+        mint,
+        proposer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const vote = (nonce: number, member: Keypair) =>
+    program.methods
+      .voteUpdate()
+      .accounts({
+        governance: governancePDA,
+        proposal: findProposalPda(nonce),
+        mint,
+        member: member.publicKey,
+      })
+      .signers([member])
+      .rpc({ commitment: "confirmed" });
+
+  const execute = (nonce: number, feeDestination: PublicKey | null = null) =>
+    program.methods
+      .executeUpdate()
+      .accounts({
+        metadata: metadataPDA,
+        governance: governancePDA,
+        proposal: findProposalPda(nonce),
+        proposer: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        feeDestination,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const createGovernance = async () => {
+    [governancePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("governance"), mint.toBuffer()],
+      program.programId
+    );
+
+    // The wallet is a member so it can propose, 2 of 4 approvals required
+    await program.methods
+      .createGovernance(
+        [wallet.publicKey, ...members.map((member) => member.publicKey)],
+        2
+      )
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        governance: governancePDA,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+  };
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await createGovernance();
+  });
+
+  it("Hands the update authority to the governance", async () => {
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.updateAuthority.toBase58()).to.equal(
+      governancePDA.toBase58()
+    );
+  });
+
+  it("Executes a proposal reaching the threshold", async () => {
+    await propose(0, "approved name");
+    await vote(0, members[0]);
+    await vote(0, members[1]);
+    await execute(0);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("approved name");
+    expect(await connection.getAccountInfo(findProposalPda(0), "confirmed")).to
+      .be.null;
+  });
+
+  it("Rejects a proposal falling short of the threshold", async () => {
+    await propose(1, "rejected name");
+    await vote(1, members[0]);
+
+    try {
+      await execute(1);
+      expect.fail("Execute should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("ThresholdNotMet");
+    }
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("approved name");
+  });
+
+  it("Rejects duplicate votes", async () => {
+    try {
+      await vote(1, members[0]);
+      expect.fail("Vote should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("AlreadyVoted");
+    }
+  });
+
+  it("Rejects duplicate members", async () => {
+    const fresh = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "symbol",
+      uri: "uri",
+    });
+    const create = (members: PublicKey[], threshold: number) =>
+      program.methods
+        .createGovernance(members, threshold)
+        .accounts({
+          metadata: fresh.metadataPDA,
+          updateAuthority: wallet.publicKey,
+          governance: PublicKey.findProgramAddressSync(
+            [Buffer.from("governance"), fresh.mint.toBuffer()],
+            program.programId
+          )[0],
+          mint: fresh.mint,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    // Three entries for two members, a threshold of 3 could never be met
+    const [a, b] = members.map((member) => member.publicKey);
+    for (const threshold of [2, 3]) {
+      try {
+        await create([a, b, a], threshold);
+        expect.fail("Create should have failed");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("DuplicateMember");
+      }
+    }
+    try {
+      await create([a, b], 3);
+      expect.fail("Create should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidThreshold");
+    }
+  });
+
+  it("Charges the update fee on execution", async () => {
+    const fee = 5000;
+    const feeDestination = Keypair.generate().publicKey;
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { updateFee: { lamports: new BN(fee), destination: feeDestination } }
+    ));
+    await createGovernance();
+    const rentExempt = await connection.getMinimumBalanceForRentExemption(0);
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(feeDestination, rentExempt),
+      "confirmed"
+    );
+
+    await propose(0, "paid name");
+    await vote(0, members[0]);
+    await vote(0, members[1]);
+    try {
+      await execute(0);
+      expect.fail("Execute should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("IncorrectFeeDestination");
+    }
+
+    await execute(0, feeDestination);
+    expect((await fetchMetadata(connection, metadataPDA)).name).to.equal(
+      "paid name"
+    );
+    expect(await connection.getBalance(feeDestination, "confirmed")).to.equal(
+      rentExempt + fee
+    );
+  });
+});
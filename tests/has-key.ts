@@ -0,0 +1,32 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram, updateField } from "./utils";
+
+describe("has key", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const hasKey = (key: string): Promise<boolean> =>
+    program.methods.hasKey(key).accounts({ metadata: metadataPDA, mint }).view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+  });
+
+  it("Returns true for a present key", async () => {
+    expect(await hasKey("key1")).to.be.true;
+  });
+
+  it("Returns false for an absent key", async () => {
+    expect(await hasKey("key2")).to.be.false;
+  });
+});
@@ -0,0 +1,38 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createMintWithMetadata, getProgram, updateField } from "./utils";
+
+describe("idempotent update", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const computeUnits = async (signature: string) =>
+    (
+      await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      })
+    ).meta.computeUnitsConsumed;
+
+  it("Skips repacking when the value is unchanged", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    const changedUnits = await computeUnits(
+      await updateField(program, wallet.payer, mint, "key1", "value1")
+    );
+    const before = await connection.getAccountInfo(metadataPDA, "confirmed");
+
+    const sameUnits = await computeUnits(
+      await updateField(program, wallet.payer, mint, "key1", "value1")
+    );
+    const after = await connection.getAccountInfo(metadataPDA, "confirmed");
+
+    expect(sameUnits).to.be.lessThan(changedUnits);
+    expect(after.data).to.deep.equal(before.data);
+    expect(after.lamports).to.equal(before.lamports);
+  });
+});
@@ -0,0 +1,52 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey } from "@solana/web3.js";
+import {
+  createMintWithPolicy,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("get metadata if newer", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+  let updatedSlot: number;
+
+  const getMetadataIfNewer = (sinceSlot: number) =>
+    program.methods
+      .getMetadataIfNewer(new BN(sinceSlot))
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { trackUpdates: true }
+    ));
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    updatedSlot = Number(getKey(metadata, "__updated_slot"));
+  });
+
+  it("Returns the metadata if modified since the slot", async () => {
+    const metadata = await getMetadataIfNewer(updatedSlot);
+    expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+    expect(
+      metadata.additionalMetadata.find(({ key }) => key === "key1").value
+    ).to.equal("value1");
+  });
+
+  it("Returns null if not modified since the slot", async () => {
+    expect(await getMetadataIfNewer(updatedSlot + 1)).to.be.null;
+  });
+});
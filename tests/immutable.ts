@@ -0,0 +1,68 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Field } from "@solana/spl-token-metadata";
+import { SystemProgram } from "@solana/web3.js";
+import {
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("initialize immutable", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const initializeImmutable = async (withUpdateAuthority = false) => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const metadataPDA = findMetadataPda(program.programId, mint);
+    await program.methods
+      .initializeImmutable({ name: "name", symbol: "symbol", uri: "uri" })
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: withUpdateAuthority ? wallet.publicKey : null,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+    return { mint, metadataPDA };
+  };
+
+  it("Creates metadata without an update authority", async () => {
+    const { mint, metadataPDA } = await initializeImmutable();
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("name");
+    expect(metadata.updateAuthority).to.be.undefined;
+
+    for (const [field, value] of [
+      [Field.Name, "new name"],
+      ["key1", "value1"],
+    ]) {
+      try {
+        await updateField(program, wallet.payer, mint, field, value);
+        expect.fail("Update should have failed");
+      } catch (error) {
+        // TokenMetadataError::ImmutableMetadata
+        expect(error.message).to.contain("0x35c2b5c1");
+      }
+    }
+  });
+
+  it("Rejects an update authority", async () => {
+    try {
+      await initializeImmutable(true);
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidUpdateAuthority");
+    }
+  });
+});
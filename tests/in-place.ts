@@ -0,0 +1,63 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram, updateField } from "./utils";
+
+describe("in place update", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  // Update the key, returns whether it was packed in place and the size change
+  const update = async (value: string) => {
+    const before = await connection.getAccountInfo(metadataPDA, "confirmed");
+    const signature = await updateField(
+      program,
+      wallet.payer,
+      mint,
+      "timestamp",
+      value
+    );
+    const after = await connection.getAccountInfo(metadataPDA, "confirmed");
+    const transaction = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    return {
+      inPlace: transaction.meta.logMessages.some((log) =>
+        log.includes("Packing metadata in place")
+      ),
+      sizeChange: after.data.length - before.data.length,
+    };
+  };
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await update("1700000000");
+  });
+
+  it("Packs an equal-length value in place", async () => {
+    // The base58 integrity hash is occasionally one char shorter, so the
+    // account size can still change, try a few values to see both cases
+    let inPlaceCount = 0;
+    for (let i = 1; i <= 4; i++) {
+      const { inPlace, sizeChange } = await update(`170000000${i}`);
+      expect(inPlace).to.equal(sizeChange === 0);
+      inPlaceCount += inPlace ? 1 : 0;
+    }
+    expect(inPlaceCount).to.be.above(0);
+  });
+
+  it("Reallocates a different-length value", async () => {
+    const { inPlace, sizeChange } = await update("1700000000000");
+    expect(inPlace).to.be.false;
+    expect(sizeChange).to.be.above(0);
+  });
+});
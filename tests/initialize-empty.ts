@@ -0,0 +1,58 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import { Field } from "@solana/spl-token-metadata";
+import {
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("initialize empty", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Reserves empty metadata and fills it in later", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const metadataPDA = findMetadataPda(program.programId, mint);
+
+    await program.methods
+      .initializeEmpty()
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+
+    let metadata = await fetchMetadata(connection, metadataPDA);
+    expect([metadata.name, metadata.symbol, metadata.uri]).to.deep.equal([
+      "",
+      "",
+      "",
+    ]);
+    const { data } = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(data.length).to.equal(12 + data.readUInt32LE(8));
+
+    await updateField(program, wallet.payer, mint, Field.Name, "Token");
+    await updateField(program, wallet.payer, mint, Field.Symbol, "TKN");
+    await updateField(program, wallet.payer, mint, Field.Uri, "https://example.com");
+
+    metadata = await fetchMetadata(connection, metadataPDA);
+    expect([metadata.name, metadata.symbol, metadata.uri]).to.deep.equal([
+      "Token",
+      "TKN",
+      "https://example.com",
+    ]);
+  });
+});
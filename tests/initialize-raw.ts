@@ -0,0 +1,127 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import {
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getKey,
+  getProgram,
+} from "./utils";
+
+// Encode pairs like the stored additional_metadata, a u32 count
+// then each key and value as u32 length prefixed UTF-8
+function encodePairs(pairs: [string, string][]): Buffer {
+  const encodeString = (value: string) => {
+    const bytes = Buffer.from(value, "utf-8");
+    const len = Buffer.alloc(4);
+    len.writeUInt32LE(bytes.length);
+    return Buffer.concat([len, bytes]);
+  };
+  const count = Buffer.alloc(4);
+  count.writeUInt32LE(pairs.length);
+  return Buffer.concat([count, ...pairs.flat().map(encodeString)]);
+}
+
+describe("initialize raw", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const initializeRaw = async (additionalMetadata: Buffer) => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const metadataPDA = findMetadataPda(program.programId, mint);
+    await program.methods
+      .initializeRaw(
+        { name: "name", symbol: "symbol", uri: "uri" },
+        additionalMetadata
+      )
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+    return metadataPDA;
+  };
+
+  it("Stores the raw pairs", async () => {
+    const metadataPDA = await initializeRaw(
+      encodePairs([
+        ["color", "blue"],
+        ["size", "large"],
+      ])
+    );
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("name");
+    expect(getKey(metadata, "color")).to.equal("blue");
+    expect(getKey(metadata, "size")).to.equal("large");
+
+    // Account is sized for the stored pairs
+    const { data } = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(data.length).to.equal(12 + data.readUInt32LE(8));
+  });
+
+  it("Rejects pairs above the size limit", async () => {
+    // 513 bytes, one past the limit
+    const additionalMetadata = encodePairs([["key1", "a".repeat(497)]]);
+    expect(additionalMetadata.length).to.equal(513);
+
+    try {
+      await initializeRaw(additionalMetadata);
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("ValueTooLong");
+    }
+  });
+
+  it("Rejects reserved keys", async () => {
+    try {
+      await initializeRaw(encodePairs([["__admin", "value"]]));
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("ReservedKey");
+    }
+  });
+
+  it("Rejects keys named like a core field", async () => {
+    try {
+      await initializeRaw(encodePairs([["name", "value"]]));
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("KeyCollidesWithCoreField");
+    }
+  });
+
+  it("Rejects a repeated key", async () => {
+    try {
+      await initializeRaw(
+        encodePairs([
+          ["color", "blue"],
+          ["color", "red"],
+        ])
+      );
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("DuplicateKey");
+    }
+  });
+
+  it("Rejects bytes that don't decode to pairs", async () => {
+    const additionalMetadata = encodePairs([["color", "blue"]]);
+    try {
+      await initializeRaw(additionalMetadata.subarray(0, -1));
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("invalid instruction data");
+    }
+  });
+});
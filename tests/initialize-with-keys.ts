@@ -0,0 +1,67 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import {
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getKey,
+  getProgram,
+} from "./utils";
+
+describe("initialize with keys", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const initializeWithKeys = async (
+    additionalMetadata: { key: string; value: string }[]
+  ) => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const metadataPDA = findMetadataPda(program.programId, mint);
+    await program.methods
+      .initializeWithKeys(
+        { name: "name", symbol: "symbol", uri: "uri" },
+        additionalMetadata
+      )
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+    return metadataPDA;
+  };
+
+  it("Initializes with unique keys", async () => {
+    const metadataPDA = await initializeWithKeys([
+      { key: "color", value: "blue" },
+      { key: "size", value: "large" },
+    ]);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("name");
+    expect(getKey(metadata, "color")).to.equal("blue");
+    expect(getKey(metadata, "size")).to.equal("large");
+  });
+
+  it("Rejects duplicate keys", async () => {
+    try {
+      await initializeWithKeys([
+        { key: "color", value: "blue" },
+        { key: "size", value: "large" },
+        { key: "color", value: "red" },
+      ]);
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("DuplicateKey");
+    }
+  });
+});
@@ -0,0 +1,298 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  AuthorityType,
+  TOKEN_2022_PROGRAM_ID,
+  createSetAuthorityInstruction,
+  setAuthority,
+} from "@solana/spl-token";
+import { createUpdateFieldInstruction } from "@solana/spl-token-metadata";
+import {
+  createInitializeMetadataInstruction,
+  createMintInstructions,
+  createMintWithMetadata,
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("initialize", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Rejects the default pubkey as update authority", async () => {
+    try {
+      await createMintWithMetadata(
+        program,
+        wallet.payer,
+        { name: "name", symbol: "symbol", uri: "uri" },
+        PublicKey.default
+      );
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      // MetadataError::InvalidUpdateAuthority
+      expect(error.message).to.contain("0x1770");
+    }
+  });
+
+  it("Rejects initialize after the mint authority is revoked", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+
+    // Disable minting
+    await setAuthority(
+      connection,
+      wallet.payer,
+      mint,
+      wallet.publicKey,
+      AuthorityType.MintTokens,
+      null,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    try {
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          createInitializeMetadataInstruction(
+            program.programId,
+            wallet.publicKey,
+            mint,
+            { name: "name", symbol: "symbol", uri: "uri" }
+          )
+        ),
+        [wallet.payer]
+      );
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      // MetadataError::MintAuthorityRevoked
+      expect(error.message).to.contain("0x1772");
+    }
+  });
+
+  it("Initializes with one signer for all roles", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const metadataPDA = findMetadataPda(program.programId, mint);
+
+    await program.methods
+      .initializeSelf({ name: "name", symbol: "symbol", uri: "uri" })
+      .accounts({
+        metadata: metadataPDA,
+        mint,
+        authority: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.updateAuthority.toBase58()).to.equal(
+      wallet.publicKey.toBase58()
+    );
+    expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+  });
+
+  it("Retries initialize after a failed transaction", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const metadataPDA = findMetadataPda(program.programId, mint);
+    const initializeInstruction = () =>
+      createInitializeMetadataInstruction(
+        program.programId,
+        wallet.publicKey,
+        mint,
+        { name: "name", symbol: "symbol", uri: "uri" }
+      );
+
+    // The second initialize fails, after the first created the account
+    try {
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(initializeInstruction(), initializeInstruction()),
+        [wallet.payer],
+        { skipPreflight: true, commitment: "confirmed" }
+      );
+      expect.fail("Transaction should have failed");
+    } catch (error) {
+      expect(error.message).to.not.contain("Transaction should have failed");
+    }
+    expect(await connection.getAccountInfo(metadataPDA, "confirmed")).to.be
+      .null;
+
+    // Retry completes initialization
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(initializeInstruction()),
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+  });
+
+  it("Rejects update field before initialize", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+
+    try {
+      await updateField(program, wallet.payer, mint, "key1", "value1");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::MetadataNotInitialized
+      expect(error.message).to.contain("0x177d");
+    }
+  });
+
+  it("Rejects a raw initialize with a non-canonical metadata account", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const initializeInstruction = createInitializeMetadataInstruction(
+      program.programId,
+      wallet.publicKey,
+      mint,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    // Replace the metadata PDA with an arbitrary address
+    const wrongMetadata = Keypair.generate();
+    initializeInstruction.keys[0] = {
+      isSigner: true,
+      isWritable: true,
+      pubkey: wrongMetadata.publicKey,
+    };
+
+    try {
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(initializeInstruction),
+        [wallet.payer, wrongMetadata]
+      );
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      // ErrorCode::ConstraintSeeds
+      expect(error.message).to.contain("0x7d6");
+    }
+    expect(
+      await connection.getAccountInfo(wrongMetadata.publicKey, "confirmed")
+    ).to.be.null;
+  });
+
+  it("Rejects a raw update field with a non-canonical metadata PDA", async () => {
+    const { mint } = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "symbol",
+      uri: "uri",
+    });
+
+    // Find a valid PDA for the same seeds below the canonical bump
+    const seeds = [Buffer.from("metadata"), mint.toBuffer()];
+    const [, canonicalBump] = PublicKey.findProgramAddressSync(
+      seeds,
+      program.programId
+    );
+    let nonCanonicalPDA: PublicKey;
+    for (let bump = canonicalBump - 1; !nonCanonicalPDA; bump--) {
+      try {
+        nonCanonicalPDA = PublicKey.createProgramAddressSync(
+          [...seeds, Buffer.from([bump])],
+          program.programId
+        );
+      } catch {
+        // On the curve, try the next bump
+      }
+    }
+
+    const updateFieldInstruction = createUpdateFieldInstruction({
+      programId: program.programId,
+      metadata: nonCanonicalPDA,
+      updateAuthority: wallet.publicKey,
+      field: "key1",
+      value: "value1",
+    });
+    updateFieldInstruction.keys.push(
+      { isSigner: false, isWritable: false, pubkey: mint },
+      { isSigner: true, isWritable: true, pubkey: wallet.publicKey },
+      { isSigner: false, isWritable: false, pubkey: SystemProgram.programId }
+    );
+
+    try {
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(updateFieldInstruction),
+        [wallet.payer]
+      );
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // ErrorCode::ConstraintSeeds
+      expect(error.message).to.contain("0x7d6");
+    }
+  });
+
+  // Create the mint, then hand its mint authority to a new key and
+  // initialize the metadata, all within one transaction
+  const initializeAfterAuthorityChange = async (signer: "old" | "new") => {
+    const mintKeypair = Keypair.generate();
+    const mint = mintKeypair.publicKey;
+    const metadataPDA = findMetadataPda(program.programId, mint);
+    const newAuthority = Keypair.generate();
+    const mintAuthority = signer === "new" ? newAuthority : wallet.payer;
+
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        ...(await createMintInstructions(
+          connection,
+          wallet.publicKey,
+          mint,
+          metadataPDA
+        )),
+        createSetAuthorityInstruction(
+          mint,
+          wallet.publicKey,
+          AuthorityType.MintTokens,
+          newAuthority.publicKey,
+          [],
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMetadataInstruction(
+          program.programId,
+          wallet.publicKey,
+          mint,
+          { name: "name", symbol: "symbol", uri: "uri" },
+          wallet.publicKey,
+          mintAuthority.publicKey
+        )
+      ),
+      [wallet.payer, mintKeypair, mintAuthority],
+      { commitment: "confirmed" }
+    );
+    return { mint, metadataPDA };
+  };
+
+  it("Initializes with a mint authority set earlier in the transaction", async () => {
+    const { mint, metadataPDA } = await initializeAfterAuthorityChange("new");
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+  });
+
+  it("Rejects the mint authority replaced earlier in the transaction", async () => {
+    try {
+      await initializeAfterAuthorityChange("old");
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      // TokenMetadataError::IncorrectMintAuthority
+      expect(error.message).to.contain("0x35c2b5bf");
+    }
+  });
+});
@@ -0,0 +1,53 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("integrity", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: anchor.web3.PublicKey;
+  let metadataPDA: anchor.web3.PublicKey;
+  let initialHash: string;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Stores integrity hash on initialize", async () => {
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    initialHash = getKey(metadata, "__integrity");
+    expect(initialHash).to.not.be.undefined;
+
+    const valid = await program.methods
+      .verifyIntegrity()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(valid).to.be.true;
+  });
+
+  it("Updates integrity hash on change", async () => {
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    const updatedHash = getKey(metadata, "__integrity");
+    expect(updatedHash).to.not.equal(initialHash);
+
+    const valid = await program.methods
+      .verifyIntegrity()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(valid).to.be.true;
+  });
+});
@@ -0,0 +1,63 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("json attributes", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setJsonAttributes = (json: string) =>
+    program.methods
+      .setJsonAttributes(json)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const getJsonAttributes = () =>
+    program.methods
+      .getJsonAttributes()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Returns no attributes until they are set", async () => {
+    expect(await getJsonAttributes()).to.be.null;
+  });
+
+  it("Stores well-formed JSON", async () => {
+    const json = JSON.stringify({
+      traits: [{ type: "color", value: "blue" }, { type: "level", value: 3 }],
+      rare: true,
+      score: -1.5e2,
+    });
+    await setJsonAttributes(json);
+    expect(await getJsonAttributes()).to.equal(json);
+  });
+
+  it("Rejects malformed JSON", async () => {
+    for (const json of ['{"traits": [1, 2,]}', "{'a': 1}", "01", '{"a": 1} x']) {
+      try {
+        await setJsonAttributes(json);
+        expect.fail("Set should have failed");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("InvalidJson");
+      }
+    }
+  });
+});
@@ -0,0 +1,113 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findLabeledMetadataPda,
+  getProgram,
+} from "./utils";
+
+describe("labeled metadata", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+
+  before(async () => {
+    ({ mint } = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "symbol",
+      uri: "uri",
+    }));
+  });
+
+  for (const [label, name] of [
+    ["en", "Token"],
+    ["ja", "トークン"],
+  ]) {
+    it(`Initializes "${label}" metadata`, async () => {
+      const metadataPDA = findLabeledMetadataPda(
+        program.programId,
+        mint,
+        label
+      );
+      await program.methods
+        .initializeLabeled(label, { name, symbol: "TKN", uri: "uri" })
+        .accounts({
+          metadata: metadataPDA,
+          updateAuthority: wallet.publicKey,
+          mint,
+          mintAuthority: wallet.publicKey,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+          config: findConfigPda(program.programId),
+          approval: findApprovalPda(program.programId, mint),
+        })
+        .rpc({ commitment: "confirmed" });
+
+      const metadata = await fetchMetadata(connection, metadataPDA);
+      expect(metadata.name).to.equal(name);
+      expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+    });
+  }
+
+  it("Updates labeled metadata independently", async () => {
+    const enPDA = findLabeledMetadataPda(program.programId, mint, "en");
+    const jaPDA = findLabeledMetadataPda(program.programId, mint, "ja");
+
+    await program.methods
+      .updateFieldLabeled("en", { field: { name: {} }, value: "English Token" })
+      .accounts({
+        metadata: enPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    expect((await fetchMetadata(connection, enPDA)).name).to.equal(
+      "English Token"
+    );
+    expect((await fetchMetadata(connection, jaPDA)).name).to.equal(
+      "トークン"
+    );
+  });
+
+  it("Reads labeled metadata", async () => {
+    const view = await program.methods
+      .getMetadataLabeled("ja")
+      .accounts({
+        metadata: findLabeledMetadataPda(program.programId, mint, "ja"),
+        mint,
+      })
+      .view();
+    expect(view.name).to.equal("トークン");
+    expect(view.symbol).to.equal("TKN");
+  });
+
+  it("Rejects an empty label", async () => {
+    try {
+      await program.methods
+        .initializeLabeled("", { name: "name", symbol: "TKN", uri: "uri" })
+        .accounts({
+          metadata: findLabeledMetadataPda(program.programId, mint, ""),
+          updateAuthority: wallet.publicKey,
+          mint,
+          mintAuthority: wallet.publicKey,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+          config: findConfigPda(program.programId),
+          approval: findApprovalPda(program.programId, mint),
+        })
+        .rpc();
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidLabel");
+    }
+  });
+});
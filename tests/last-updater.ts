@@ -0,0 +1,58 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram, updateField } from "./utils";
+
+describe("last updater", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  const delegate = Keypair.generate();
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const getLastUpdater = () =>
+    program.methods
+      .getLastUpdater()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await program.methods
+      .setDelegate(delegate.publicKey, false)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+  });
+
+  it("Returns no updater before the first update", async () => {
+    expect(await getLastUpdater()).to.be.null;
+  });
+
+  it("Records the signer of each update", async () => {
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+    expect((await getLastUpdater()).toBase58()).to.equal(
+      wallet.publicKey.toBase58()
+    );
+
+    await updateField(program, wallet.payer, mint, "key1", "value2", delegate);
+    expect((await getLastUpdater()).toBase58()).to.equal(
+      delegate.publicKey.toBase58()
+    );
+
+    await updateField(program, wallet.payer, mint, "key2", "value1");
+    expect((await getLastUpdater()).toBase58()).to.equal(
+      wallet.publicKey.toBase58()
+    );
+  });
+});
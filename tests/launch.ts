@@ -0,0 +1,70 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  getMint,
+} from "@solana/spl-token";
+import {
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getProgram,
+} from "./utils";
+
+describe("launch", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const launch = async (tokenProgram: PublicKey) => {
+    const mintKeypair = Keypair.generate();
+    const mint = mintKeypair.publicKey;
+    const metadataPDA = findMetadataPda(program.programId, mint);
+
+    await program.methods
+      .createMintAndMetadata(6, { name: "name", symbol: "symbol", uri: "uri" })
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        tokenProgram,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .signers([mintKeypair])
+      .rpc({ commitment: "confirmed" });
+    return { mint, metadataPDA };
+  };
+
+  for (const [label, tokenProgram] of [
+    ["Token", TOKEN_PROGRAM_ID],
+    ["Token-2022", TOKEN_2022_PROGRAM_ID],
+  ] as const) {
+    it(`Launches a ${label} mint with metadata in one transaction`, async () => {
+      const { mint, metadataPDA } = await launch(tokenProgram);
+
+      const mintAccount = await getMint(
+        connection,
+        mint,
+        "confirmed",
+        tokenProgram
+      );
+      expect(mintAccount.decimals).to.equal(6);
+      expect(mintAccount.mintAuthority.toBase58()).to.equal(
+        wallet.publicKey.toBase58()
+      );
+
+      const metadata = await fetchMetadata(connection, metadataPDA);
+      expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+      expect(metadata.name).to.equal("name");
+      expect(metadata.symbol).to.equal("symbol");
+      expect(metadata.uri).to.equal("uri");
+    });
+  }
+});
@@ -0,0 +1,90 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createHash } from "crypto";
+import { PublicKey } from "@solana/web3.js";
+import { TokenMetadata } from "@solana/spl-token-metadata";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  updateField,
+} from "./utils";
+
+const sha256 = (...parts: Buffer[]) => {
+  const hasher = createHash("sha256");
+  parts.forEach((part) => hasher.update(part));
+  return hasher.digest();
+};
+
+// Borsh encoding of a string, u32 length then bytes
+const borshString = (value: string) => {
+  const bytes = Buffer.from(value, "utf-8");
+  const len = Buffer.alloc(4);
+  len.writeUInt32LE(bytes.length);
+  return Buffer.concat([len, bytes]);
+};
+
+// Leaf hash following the scheme documented on export_leaf
+function leafHash(metadata: TokenMetadata): number[] {
+  const entries = metadata.additionalMetadata
+    .filter(([key]) => key !== "__integrity")
+    // Byte order by key, then value
+    .sort(
+      ([a, aValue], [b, bValue]) =>
+        Buffer.compare(Buffer.from(a), Buffer.from(b)) ||
+        Buffer.compare(Buffer.from(aValue), Buffer.from(bValue))
+    );
+  const count = Buffer.alloc(4);
+  count.writeUInt32LE(entries.length);
+  const additionalMetadata = Buffer.concat([
+    count,
+    ...entries.flatMap(([key, value]) => [borshString(key), borshString(value)]),
+  ]);
+
+  return [
+    ...sha256(
+      Buffer.from([0]),
+      metadata.mint.toBuffer(),
+      (metadata.updateAuthority ?? PublicKey.default).toBuffer(),
+      sha256(Buffer.from(metadata.name)),
+      sha256(Buffer.from(metadata.symbol)),
+      sha256(Buffer.from(metadata.uri)),
+      sha256(additionalMetadata)
+    ),
+  ];
+}
+
+describe("export leaf", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const exportLeaf = (): Promise<number[]> =>
+    program.methods.exportLeaf().accounts({ metadata: metadataPDA, mint }).view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+  });
+
+  it("Returns a stable leaf matching the documented scheme", async () => {
+    const leaf = await exportLeaf();
+    expect(await exportLeaf()).to.deep.equal(leaf);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(leaf).to.deep.equal(leafHash(metadata));
+  });
+
+  it("Changes the leaf when a field changes", async () => {
+    const before = await exportLeaf();
+    await updateField(program, wallet.payer, mint, "key1", "value2");
+    expect(await exportLeaf()).to.not.deep.equal(before);
+  });
+});
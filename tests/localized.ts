@@ -0,0 +1,62 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("localized", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setLocalized = (locale: string, name: string, symbol: string) =>
+    program.methods
+      .setLocalized(locale, name, symbol)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const getLocalized = (locale: string) =>
+    program.methods
+      .getLocalized(locale)
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "Token", symbol: "TKN", uri: "uri" }
+    ));
+  });
+
+  it("Returns the variant set for a locale", async () => {
+    await setLocalized("ja", "トークン", "トークン");
+    expect(await getLocalized("ja")).to.deep.equal({
+      name: "トークン",
+      symbol: "トークン",
+    });
+  });
+
+  it("Falls back to the default for an unset locale", async () => {
+    expect(await getLocalized("fr-CA")).to.deep.equal({
+      name: "Token",
+      symbol: "TKN",
+    });
+  });
+
+  it("Rejects an invalid locale", async () => {
+    try {
+      await setLocalized("japanese", "トークン", "トークン");
+      expect.fail("Set should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidLocale");
+    }
+  });
+});
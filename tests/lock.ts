@@ -0,0 +1,83 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("lock", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const accounts = () => ({
+    metadata: metadataPDA,
+    updateAuthority: wallet.publicKey,
+    mint,
+    payer: wallet.publicKey,
+    systemProgram: SystemProgram.programId,
+  });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Rejects updates to a locked field", async () => {
+    await program.methods
+      .lockField({ symbol: {} }, false)
+      .accounts(accounts())
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await updateField(program, wallet.payer, mint, "symbol", "NEW");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::FieldLocked
+      expect(error.message).to.contain("0x177e");
+    }
+
+    // Other fields remain editable
+    await updateField(program, wallet.payer, mint, "name", "new name");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("new name");
+    expect(metadata.symbol).to.equal("symbol");
+  });
+
+  it("Unlocks a reversible lock", async () => {
+    await program.methods
+      .unlockField({ symbol: {} })
+      .accounts(accounts())
+      .rpc({ commitment: "confirmed" });
+
+    await updateField(program, wallet.payer, mint, "symbol", "NEW");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.symbol).to.equal("NEW");
+  });
+
+  it("Keeps a permanent lock", async () => {
+    await program.methods
+      .lockField({ symbol: {} }, true)
+      .accounts(accounts())
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await program.methods
+        .unlockField({ symbol: {} })
+        .accounts(accounts())
+        .rpc({ commitment: "confirmed" });
+      expect.fail("Unlock should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("FieldLocked");
+    }
+  });
+});
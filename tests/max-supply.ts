@@ -0,0 +1,81 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("max supply", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setMaxSupply = (maxSupply: number) =>
+    program.methods
+      .setMaxSupply(new BN(maxSupply))
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+
+    // Current supply of 100
+    const tokenAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      wallet.payer,
+      mint,
+      wallet.publicKey,
+      false,
+      "confirmed",
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      connection,
+      wallet.payer,
+      mint,
+      tokenAccount.address,
+      wallet.payer,
+      100,
+      [],
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  it("Sets a max supply", async () => {
+    await setMaxSupply(1000);
+
+    const maxSupply = await program.methods
+      .getMaxSupply()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(maxSupply.toNumber()).to.equal(1000);
+  });
+
+  it("Rejects a max supply below the current supply", async () => {
+    try {
+      await setMaxSupply(99);
+      expect.fail("Set max supply should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("MaxSupplyBelowSupply");
+    }
+  });
+});
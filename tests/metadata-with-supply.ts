@@ -0,0 +1,61 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  getMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("metadata with supply", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Returns the metadata with the mint's supply and decimals", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    const tokenAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      wallet.payer,
+      mint,
+      wallet.publicKey,
+      false,
+      "confirmed",
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      connection,
+      wallet.payer,
+      mint,
+      tokenAccount.address,
+      wallet.payer,
+      1234,
+      [],
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const result = await program.methods
+      .getMetadataWithSupply()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    const mintInfo = await getMint(
+      connection,
+      mint,
+      "confirmed",
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    expect(result.metadata.name).to.equal("name");
+    expect(result.metadata.mint.toBase58()).to.equal(mint.toBase58());
+    expect(result.supply.toString()).to.equal(mintInfo.supply.toString());
+    expect(result.supply.toNumber()).to.equal(1234);
+    expect(result.decimals).to.equal(mintInfo.decimals);
+  });
+});
@@ -0,0 +1,68 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("metaplex export", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const exportMetaplex = () =>
+    program.methods
+      .exportMetaplex()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "SYM", uri: "https://example.com" }
+    ));
+  });
+
+  it("Exports core fields without royalties", async () => {
+    const data = await exportMetaplex();
+    expect(data.name).to.equal("name");
+    expect(data.symbol).to.equal("SYM");
+    expect(data.uri).to.equal("https://example.com");
+    expect(data.sellerFeeBasisPoints).to.equal(0);
+    expect(data.creators).to.be.null;
+  });
+
+  it("Exports royalties and creators", async () => {
+    const creators = [
+      { address: Keypair.generate().publicKey, share: 60 },
+      { address: Keypair.generate().publicKey, share: 40 },
+    ];
+    await program.methods
+      .setRoyalties(500, creators)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const data = await exportMetaplex();
+    expect(data.sellerFeeBasisPoints).to.equal(500);
+    expect(
+      data.creators.map((creator) => ({
+        address: creator.address.toBase58(),
+        verified: creator.verified,
+        share: creator.share,
+      }))
+    ).to.deep.equal(
+      creators.map((creator) => ({
+        address: creator.address.toBase58(),
+        verified: false,
+        share: creator.share,
+      }))
+    );
+  });
+});
@@ -0,0 +1,78 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { getProgram } from "./utils";
+import fs from "fs";
+
+describe("migrate proposal", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  // Unversioned proposal for the legacy mint, preloaded by the test validator
+  const mint = new PublicKey("Cumo6wgZV9JrdRtCgoxMjBduC2t38GkLGSHTi8GNLUUh");
+  const proposal = new PublicKey(
+    "CJTm9j6ciXxpUGCnE3WJU89NqCyCGsxgapdMmtZmjPAb"
+  );
+  const proposer = Keypair.fromSecretKey(
+    Uint8Array.from(
+      JSON.parse(
+        fs.readFileSync("tests/fixtures/legacy-authority.json", "utf-8")
+      )
+    )
+  ).publicKey;
+
+  const migrateProposal = () =>
+    program.methods
+      .migrateProposal()
+      .accounts({
+        proposal,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  it("Reads the proposal in the old layout", async () => {
+    const legacy = await program.account.proposal.fetch(proposal);
+    expect(legacy.mint.toBase58()).to.equal(mint.toBase58());
+
+    // Not readable as the versioned layout before migration
+    try {
+      await program.account.versionedProposal.fetch(proposal);
+      expect.fail("Fetch should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("Invalid account discriminator");
+    }
+  });
+
+  it("Migrates the proposal to the versioned layout", async () => {
+    const before = await connection.getAccountInfo(proposal, "confirmed");
+    await migrateProposal();
+    const after = await connection.getAccountInfo(proposal, "confirmed");
+    // Grown by the version byte
+    expect(after.data.length).to.equal(before.data.length + 1);
+
+    const migrated = await program.account.versionedProposal.fetch(proposal);
+    expect(migrated.version).to.equal(1);
+    expect(migrated.mint.toBase58()).to.equal(mint.toBase58());
+    expect(migrated.proposer.toBase58()).to.equal(proposer.toBase58());
+    expect(migrated.nonce.toNumber()).to.equal(1);
+    expect(migrated.threshold).to.equal(2);
+    expect(migrated.approvals.map((key) => key.toBase58())).to.deep.equal([
+      proposer.toBase58(),
+    ]);
+    expect(migrated.data.field).to.deep.equal({ key: ["key1"] });
+    expect(migrated.data.value).to.equal("proposed");
+  });
+
+  it("Rejects migrating twice", async () => {
+    try {
+      await migrateProposal();
+      expect.fail("Migration should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal(
+        "AccountDiscriminatorMismatch"
+      );
+    }
+  });
+});
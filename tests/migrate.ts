@@ -0,0 +1,133 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findLabeledMetadataPda,
+  findMetadataPda,
+  getKey,
+  getProgram,
+} from "./utils";
+import fs from "fs";
+
+describe("migrate pda", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  // Accounts preloaded by the test validator, see Anchor.toml,
+  // the metadata at the legacy ["token-metadata", mint] PDA
+  const mint = new PublicKey("Cumo6wgZV9JrdRtCgoxMjBduC2t38GkLGSHTi8GNLUUh");
+  const oldMetadata = new PublicKey(
+    "97k3MuvjVNhKrZEtZHMkJUYEbgPjqHXQqbmSU9TJcJCS"
+  );
+  const legacyAuthority = Keypair.fromSecretKey(
+    Uint8Array.from(
+      JSON.parse(
+        fs.readFileSync("tests/fixtures/legacy-authority.json", "utf-8")
+      )
+    )
+  );
+  const metadataPDA = findMetadataPda(program.programId, mint);
+
+  it("Rejects migration without the update authority", async () => {
+    try {
+      await program.methods
+        .migratePda()
+        .accounts({
+          oldMetadata,
+          metadata: metadataPDA,
+          updateAuthority: wallet.publicKey,
+          mint,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Migration should have failed");
+    } catch (error) {
+      // TokenMetadataError::IncorrectUpdateAuthority
+      expect(error.message).to.contain("0x35c2b5c0");
+    }
+  });
+
+  it("Rejects an account not at the legacy address", async () => {
+    // Labeled metadata is program owned with a matching mint and authority
+    const otherMint = await createPointerMint(program, wallet.payer);
+    const labeledPDA = findLabeledMetadataPda(
+      program.programId,
+      otherMint,
+      "en"
+    );
+    await program.methods
+      .initializeLabeled("en", { name: "name", symbol: "TKN", uri: "uri" })
+      .accounts({
+        metadata: labeledPDA,
+        updateAuthority: wallet.publicKey,
+        mint: otherMint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, otherMint),
+      })
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await program.methods
+        .migratePda()
+        .accounts({
+          oldMetadata: labeledPDA,
+          metadata: findMetadataPda(program.programId, otherMint),
+          updateAuthority: wallet.publicKey,
+          mint: otherMint,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Migration should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("ConstraintSeeds");
+    }
+  });
+
+  it("Migrates metadata from the old address to canonical PDA", async () => {
+    const oldData = (await connection.getAccountInfo(oldMetadata)).data;
+
+    await program.methods
+      .migratePda()
+      .accounts({
+        oldMetadata,
+        metadata: metadataPDA,
+        updateAuthority: legacyAuthority.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([legacyAuthority])
+      .rpc({ commitment: "confirmed" });
+
+    // Check data was copied as is
+    const newAccount = await connection.getAccountInfo(
+      metadataPDA,
+      "confirmed"
+    );
+    expect(newAccount.owner.toBase58()).to.equal(program.programId.toBase58());
+    expect(Buffer.compare(newAccount.data, oldData)).to.equal(0);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("legacy name");
+    expect(metadata.symbol).to.equal("LEGACY");
+    expect(metadata.mint.toBase58()).to.equal(mint.toBase58());
+    expect(getKey(metadata, "key1")).to.equal("value1");
+
+    // Check the old account was closed
+    const oldAccount = await connection.getAccountInfo(
+      oldMetadata,
+      "confirmed"
+    );
+    expect(oldAccount).to.be.null;
+  });
+});
@@ -0,0 +1,60 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("move key", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Moves a key between two mints", async () => {
+    const source = await createMintWithMetadata(program, wallet.payer, {
+      name: "source",
+      symbol: "SRC",
+      uri: "uri",
+    });
+    const destination = await createMintWithMetadata(program, wallet.payer, {
+      name: "destination",
+      symbol: "DST",
+      uri: "uri",
+    });
+    await updateField(program, wallet.payer, source.mint, "family", "value");
+
+    await program.methods
+      .moveKey("family")
+      .accounts({
+        sourceMetadata: source.metadataPDA,
+        sourceAuthority: wallet.publicKey,
+        sourceMint: source.mint,
+        destinationMetadata: destination.metadataPDA,
+        destinationAuthority: wallet.publicKey,
+        destinationMint: destination.mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const sourceMetadata = await fetchMetadata(connection, source.metadataPDA);
+    const destinationMetadata = await fetchMetadata(
+      connection,
+      destination.metadataPDA
+    );
+    expect(getKey(sourceMetadata, "family")).to.be.undefined;
+    expect(getKey(destinationMetadata, "family")).to.equal("value");
+
+    // Both accounts stay exactly rent exempt
+    for (const pda of [source.metadataPDA, destination.metadataPDA]) {
+      const account = await connection.getAccountInfo(pda, "confirmed");
+      expect(account.lamports).to.equal(
+        await connection.getMinimumBalanceForRentExemption(account.data.length)
+      );
+    }
+  });
+});
@@ -0,0 +1,96 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithPolicy,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+// Must match MAX_VALUE_LEN in the program
+const MAX_VALUE_LEN = 512;
+
+describe("oracle", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const oracle = Keypair.generate();
+  const newOracle = Keypair.generate();
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const oracleSetField = (signer: Keypair, key: string, value: string) =>
+    program.methods
+      .oracleSetField(key, value)
+      .accounts({
+        metadata: metadataPDA,
+        oracle: signer.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([signer])
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { uppercaseSymbol: null, oracle: oracle.publicKey }
+    ));
+  });
+
+  it("Oracle writes its keys", async () => {
+    await oracleSetField(oracle, "price", "42");
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "__oracle.price")).to.equal("42");
+  });
+
+  it("Rejects an oracle value over the length limit", async () => {
+    try {
+      await oracleSetField(oracle, "price", "v".repeat(MAX_VALUE_LEN + 1));
+      expect.fail("Oracle write should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("ValueTooLong");
+    }
+  });
+
+  it("Blocks users from oracle keys", async () => {
+    try {
+      await updateField(program, wallet.payer, mint, "__oracle.price", "0");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::ReservedKey
+      expect(error.message).to.contain("0x1775");
+    }
+  });
+
+  it("Rotates the oracle", async () => {
+    await program.methods
+      .setOracle(newOracle.publicKey)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await oracleSetField(oracle, "price", "43");
+      expect.fail("Old oracle should be rejected");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("IncorrectOracle");
+    }
+
+    await oracleSetField(newOracle, "price", "44");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "__oracle.price")).to.equal("44");
+  });
+});
@@ -0,0 +1,82 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  simulateReturnData,
+  updateField,
+} from "./utils";
+
+describe("get metadata page", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const getPage = (offset: number, limit: number) =>
+    program.methods
+      .getMetadataPage(offset, limit)
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    for (let i = 0; i < 20; i++) {
+      const index = String(i).padStart(2, "0");
+      await updateField(
+        program,
+        wallet.payer,
+        mint,
+        `key${index}`,
+        `v${index}`
+      );
+    }
+  });
+
+  it("Pages through every custom key", async () => {
+    const entries: { key: string; value: string }[] = [];
+    let total: number;
+    for (let offset = 0; total === undefined || offset < total; offset += 5) {
+      const page = await getPage(offset, 5);
+      expect(page.metadata.name).to.equal("name");
+      expect(page.metadata.additionalMetadata.length).to.be.at.most(5);
+      entries.push(...page.metadata.additionalMetadata);
+      total = page.total;
+    }
+
+    // Same entries as the account, in sorted order
+    const stored = (await fetchMetadata(connection, metadataPDA))
+      .additionalMetadata.map(([key, value]) => ({ key, value }))
+      .sort((a, b) => (a.key < b.key ? -1 : a.key > b.key ? 1 : 0));
+    expect(entries).to.deep.equal(stored);
+    const custom = entries.filter(({ key }) => !key.startsWith("__"));
+    expect(custom).to.have.length(20);
+  });
+
+  it("Returns an empty page past the end", async () => {
+    const page = await getPage(1000, 5);
+    expect(page.metadata.additionalMetadata).to.be.empty;
+  });
+
+  it("Rejects a limit above the maximum", async () => {
+    const transaction = await program.methods
+      .getMetadataPage(0, 11)
+      .accounts({ metadata: metadataPDA, mint })
+      .transaction();
+    try {
+      await simulateReturnData(program, transaction, wallet.payer);
+      expect.fail("Read should have failed");
+    } catch (error) {
+      // MetadataError::TooManyKeys
+      expect(error.message).to.contain('"Custom":6031');
+    }
+  });
+});
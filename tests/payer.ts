@@ -0,0 +1,104 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Keypair,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import { createUpdateFieldInstruction } from "@solana/spl-token-metadata";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("payer", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Rejects the metadata account as payer", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    const updateFieldInstruction = createUpdateFieldInstruction({
+      programId: program.programId,
+      metadata: metadataPDA,
+      updateAuthority: wallet.publicKey,
+      field: "key1",
+      value: "value1",
+    });
+    // The PDA can't sign a transaction, so it is passed without a signature
+    updateFieldInstruction.keys.push(
+      { isSigner: false, isWritable: false, pubkey: mint },
+      { isSigner: false, isWritable: true, pubkey: metadataPDA },
+      { isSigner: false, isWritable: false, pubkey: SystemProgram.programId }
+    );
+
+    try {
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(updateFieldInstruction),
+        [wallet.payer],
+        { commitment: "confirmed" }
+      );
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // ErrorCode::AccountNotSigner
+      expect(error.message).to.contain("0xbc2");
+    }
+  });
+
+  it("Rejects a growth the payer can't fund with a clear error", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    // Enough to exist, far less than the rent of the grown account
+    const payer = Keypair.generate();
+    const lamports = await connection.getMinimumBalanceForRentExemption(0);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: wallet.publicKey,
+          toPubkey: payer.publicKey,
+          lamports,
+        })
+      ),
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+
+    const updateFieldInstruction = createUpdateFieldInstruction({
+      programId: program.programId,
+      metadata: metadataPDA,
+      updateAuthority: wallet.publicKey,
+      field: "key1",
+      value: "a".repeat(500),
+    });
+    updateFieldInstruction.keys.push(
+      { isSigner: false, isWritable: false, pubkey: mint },
+      { isSigner: true, isWritable: true, pubkey: payer.publicKey },
+      { isSigner: false, isWritable: false, pubkey: SystemProgram.programId }
+    );
+
+    try {
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(updateFieldInstruction),
+        [wallet.payer, payer],
+        { commitment: "confirmed" }
+      );
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::PayerInsufficientFunds
+      expect(error.message).to.contain("0x17a9");
+    }
+    expect(await connection.getBalance(payer.publicKey, "confirmed")).to.equal(
+      lamports
+    );
+  });
+});
@@ -0,0 +1,402 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { Field } from "@solana/spl-token-metadata";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithPolicy,
+  createPointerMint,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("policy", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const initializeWithPolicy = (symbol: string, policy: object) =>
+    createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol, uri: "uri" },
+      policy
+    );
+
+  it("Rejects a lowercase symbol in reject mode", async () => {
+    try {
+      await initializeWithPolicy("tkn", { uppercaseSymbol: { reject: {} } });
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("SymbolNotUppercase");
+    }
+  });
+
+  it("Accepts an uppercase symbol and rejects lowercase updates", async () => {
+    const { mint, metadataPDA } = await initializeWithPolicy("TKN", {
+      uppercaseSymbol: { reject: {} },
+    });
+    expect((await fetchMetadata(connection, metadataPDA)).symbol).to.equal(
+      "TKN"
+    );
+
+    try {
+      await updateField(program, wallet.payer, mint, Field.Symbol, "tkn");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::SymbolNotUppercase
+      expect(error.message).to.contain("0x1776");
+    }
+  });
+
+  it("Normalizes a lowercase symbol in normalize mode", async () => {
+    const { mint, metadataPDA } = await initializeWithPolicy("tkn", {
+      uppercaseSymbol: { normalize: {} },
+    });
+    expect((await fetchMetadata(connection, metadataPDA)).symbol).to.equal(
+      "TKN"
+    );
+
+    await updateField(program, wallet.payer, mint, Field.Symbol, "new");
+    expect((await fetchMetadata(connection, metadataPDA)).symbol).to.equal(
+      "NEW"
+    );
+  });
+
+  it("Rejects updating the policy through a reserved key", async () => {
+    const { mint } = await initializeWithPolicy("TKN", {
+      uppercaseSymbol: { reject: {} },
+    });
+
+    try {
+      await updateField(program, wallet.payer, mint, "__uppercase_symbol", "");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::ReservedKey
+      expect(error.message).to.contain("0x1775");
+    }
+  });
+
+  // 10 chars, 30 bytes
+  const cjkSymbol = "代币代币代币代币代币";
+
+  it("Measures the symbol length in bytes in byte mode", async () => {
+    try {
+      await initializeWithPolicy(cjkSymbol, { symbolLength: { bytes: {} } });
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("SymbolTooLong");
+    }
+  });
+
+  it("Leaves the symbol length unlimited without the policy", async () => {
+    const { mint, metadataPDA } = await initializeWithPolicy(cjkSymbol, {});
+    expect((await fetchMetadata(connection, metadataPDA)).symbol).to.equal(
+      cjkSymbol
+    );
+
+    // 11 bytes
+    await updateField(program, wallet.payer, mint, Field.Symbol, "LONGSYMBOL1");
+    expect((await fetchMetadata(connection, metadataPDA)).symbol).to.equal(
+      "LONGSYMBOL1"
+    );
+  });
+
+  it("Measures the symbol length in chars in char mode", async () => {
+    const { metadataPDA } = await initializeWithPolicy(cjkSymbol, {
+      symbolLength: { chars: {} },
+    });
+    expect((await fetchMetadata(connection, metadataPDA)).symbol).to.equal(
+      cjkSymbol
+    );
+
+    try {
+      await initializeWithPolicy(cjkSymbol + "币", {
+        symbolLength: { chars: {} },
+      });
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("SymbolTooLong");
+    }
+  });
+
+  // 40 bytes, with a 2 byte char across the 32 byte limit
+  const longName = "a".repeat(31) + "é" + "a".repeat(7);
+
+  it("Rejects a long name in reject mode", async () => {
+    try {
+      await createMintWithPolicy(
+        program,
+        wallet.payer,
+        { name: longName, symbol: "TKN", uri: "uri" },
+        { nameLimit: { reject: {} } }
+      );
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("NameTooLong");
+    }
+  });
+
+  it("Truncates a long name at a char boundary in truncate mode", async () => {
+    const { metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: longName, symbol: "TKN", uri: "uri" },
+      { nameLimit: { truncate: {} } }
+    );
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("a".repeat(31));
+
+    // Account is sized for the truncated name
+    const { data } = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(data.length).to.equal(12 + data.readUInt32LE(8));
+  });
+
+  it("Rejects updates until the supply threshold is met", async () => {
+    const { mint, metadataPDA } = await initializeWithPolicy("TKN", {
+      supplyThreshold: new BN(100),
+    });
+    const tokenAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      wallet.payer,
+      mint,
+      wallet.publicKey,
+      false,
+      "confirmed",
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const mintTokens = (amount: number) =>
+      mintTo(
+        connection,
+        wallet.payer,
+        mint,
+        tokenAccount.address,
+        wallet.payer,
+        amount,
+        [],
+        { commitment: "confirmed" },
+        TOKEN_2022_PROGRAM_ID
+      );
+
+    // Below the threshold
+    await mintTokens(99);
+    try {
+      await updateField(program, wallet.payer, mint, "key1", "value1");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::SupplyThresholdNotMet
+      expect(error.message).to.contain("0x1796");
+    }
+
+    // At the threshold
+    await mintTokens(1);
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Rejects updates after the lock time", async () => {
+    const lockAt = Math.floor(Date.now() / 1000) + 5;
+    const { mint, metadataPDA } = await initializeWithPolicy("TKN", {
+      lockAt: new BN(lockAt),
+    });
+
+    // Before the lock time
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+    expect(
+      getKey(await fetchMetadata(connection, metadataPDA), "key1")
+    ).to.equal("value1");
+
+    // Wait for the cluster clock to pass the lock time
+    while (
+      (await connection.getBlockTime(await connection.getSlot("confirmed"))) <=
+      lockAt
+    ) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+
+    try {
+      await updateField(program, wallet.payer, mint, "key1", "value2");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::MetadataLockedByTime
+      expect(error.message).to.contain("0x179b");
+    }
+  });
+
+  // Initialize a mint with the given freeze authority
+  const initializeFreezable = async (
+    freezeAuthority: PublicKey | null,
+    policy: object = { rejectFreezable: true }
+  ) => {
+    const mint = await createPointerMint(
+      program,
+      wallet.payer,
+      9,
+      freezeAuthority
+    );
+    await program.methods
+      .initializeWithPolicy({ name: "name", symbol: "TKN", uri: "uri" }, policy)
+      .accounts({
+        metadata: findMetadataPda(program.programId, mint),
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+    return mint;
+  };
+
+  it("Rejects a mint with a freeze authority when freezable mints are rejected", async () => {
+    try {
+      await initializeFreezable(wallet.publicKey);
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("MintFrozen");
+    }
+  });
+
+  it("Accepts a mint without a freeze authority when freezable mints are rejected", async () => {
+    const mint = await initializeFreezable(null);
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+  });
+
+  it("Accepts a mint with a freeze authority without the policy", async () => {
+    const mint = await initializeFreezable(wallet.publicKey, {});
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+  });
+
+  it("Rejects an empty name under a minimum name length", async () => {
+    try {
+      await createMintWithPolicy(
+        program,
+        wallet.payer,
+        { name: "", symbol: "TKN", uri: "uri" },
+        { minNameLen: 3 }
+      );
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("NameTooShort");
+    }
+  });
+
+  it("Accepts a name at the minimum length and rejects shorter updates", async () => {
+    const { mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "abc", symbol: "TKN", uri: "uri" },
+      { minNameLen: 3, minSymbolLen: 2 }
+    );
+    expect((await fetchMetadata(connection, metadataPDA)).name).to.equal(
+      "abc"
+    );
+
+    try {
+      await updateField(program, wallet.payer, mint, Field.Symbol, "T");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::SymbolTooShort
+      expect(error.message).to.contain("0x179e");
+    }
+  });
+
+  it("Accepts allowlisted keys and rejects others", async () => {
+    const { mint, metadataPDA } = await initializeWithPolicy("TKN", {
+      allowedKeys: ["color", "level"],
+    });
+
+    await updateField(program, wallet.payer, mint, "color", "blue");
+    await updateField(program, wallet.payer, mint, "level", "3");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "color")).to.equal("blue");
+    expect(getKey(metadata, "level")).to.equal("3");
+
+    try {
+      await updateField(program, wallet.payer, mint, "size", "large");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::KeyNotAllowed
+      expect(error.message).to.contain("0x17a0");
+    }
+  });
+
+  it("Accepts any key with an empty allowlist", async () => {
+    const { mint } = await initializeWithPolicy("TKN", { allowedKeys: [] });
+    await updateField(program, wallet.payer, mint, "size", "large");
+  });
+
+  it("Accepts prefixed keys and rejects others under a key prefix", async () => {
+    const { mint, metadataPDA } = await initializeWithPolicy("TKN", {
+      keyPrefix: "app1.",
+    });
+
+    await updateField(program, wallet.payer, mint, "app1.color", "blue");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "app1.color")).to.equal("blue");
+
+    for (const key of ["color", "app2.color"]) {
+      try {
+        await updateField(program, wallet.payer, mint, key, "blue");
+        expect.fail("Update should have failed");
+      } catch (error) {
+        // MetadataError::KeyPrefixRequired
+        expect(error.message).to.contain("0x17a7");
+      }
+    }
+  });
+
+  it("Grows up to the max total size and rejects updates beyond it", async () => {
+    const maxTotalSize = 400;
+    const { mint, metadataPDA } = await initializeWithPolicy("TKN", {
+      maxTotalSize,
+    });
+    const size = async () =>
+      (await connection.getAccountInfo(metadataPDA, "confirmed")).data.length;
+
+    // u32 length prefixed key and value, with a byte of slack for the
+    // integrity hash, whose base58 length varies
+    const valueLen = maxTotalSize - (await size()) - 4 - 4 - 4 - 1;
+    await updateField(program, wallet.payer, mint, "k1", "a".repeat(valueLen));
+    expect(await size()).to.be.at.most(maxTotalSize);
+
+    // Rejected even though the payer can afford the rent
+    try {
+      await updateField(program, wallet.payer, mint, "k2", "a".repeat(8));
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::MaxSizeExceeded
+      expect(error.message).to.contain("0x17a8");
+    }
+
+    // Shrinking stays within the cap
+    await updateField(program, wallet.payer, mint, "k1", "a");
+    expect(
+      getKey(await fetchMetadata(connection, metadataPDA), "k1")
+    ).to.equal("a");
+  });
+
+  it("Rejects initialize above the max total size", async () => {
+    try {
+      await initializeWithPolicy("TKN", { maxTotalSize: 100 });
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("MaxSizeExceeded");
+    }
+  });
+});
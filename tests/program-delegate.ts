@@ -0,0 +1,105 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import { createUpdateFieldInstruction } from "@solana/spl-token-metadata";
+import { AuthorityCaller } from "../target/types/authority_caller";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+} from "./utils";
+
+describe("program delegate", () => {
+  const program = getProgram();
+  const caller = anchor.workspace.AuthorityCaller as Program<AuthorityCaller>;
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const [delegate] = PublicKey.findProgramAddressSync(
+    [Buffer.from("delegate")],
+    caller.programId
+  );
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await program.methods
+      .setProgramDelegate(caller.programId, [Buffer.from("delegate")])
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+  });
+
+  it("Stores the PDA of the program as the delegate", async () => {
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "__delegate")).to.equal(delegate.toBase58());
+    expect(getKey(metadata, "__delegate.program")).to.equal(
+      caller.programId.toBase58()
+    );
+  });
+
+  it("Program updates a field through CPI as the delegate", async () => {
+    await caller.methods
+      .updateFieldAsDelegate({ field: { key: ["key1"] }, value: "value1" })
+      .accounts({
+        metadata: metadataPDA,
+        delegate,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenMetadataProgram: program.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Rejects a direct update with the delegate address", async () => {
+    const updateFieldInstruction = createUpdateFieldInstruction({
+      programId: program.programId,
+      metadata: metadataPDA,
+      updateAuthority: delegate,
+      field: "key1",
+      value: "value2",
+    });
+    // Nobody holds a key for the PDA, so it can only be passed unsigned
+    updateFieldInstruction.keys[1].isSigner = false;
+    updateFieldInstruction.keys.push(
+      { isSigner: false, isWritable: false, pubkey: mint },
+      { isSigner: true, isWritable: true, pubkey: wallet.publicKey },
+      { isSigner: false, isWritable: false, pubkey: SystemProgram.programId }
+    );
+
+    try {
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(updateFieldInstruction),
+        [wallet.payer]
+      );
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // ErrorCode::AccountNotSigner
+      expect(error.message).to.contain("0xbc2");
+    }
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+});
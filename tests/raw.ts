@@ -0,0 +1,54 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  createMintWithMetadata,
+  getProgram,
+  simulateReturnData,
+} from "./utils";
+
+describe("raw tlv", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: anchor.web3.PublicKey;
+  let metadataPDA: anchor.web3.PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Returns the TLV header bytes", async () => {
+    const transaction = await program.methods
+      .getRawTlv(0, 12)
+      .accounts({ metadata: metadataPDA, mint })
+      .transaction();
+    const data = await simulateReturnData(program, transaction, wallet.payer);
+
+    // TokenMetadata discriminator followed by the u32 length of the value
+    const account = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(data.subarray(0, 8)).to.deep.equal(
+      Buffer.from([112, 132, 90, 90, 11, 88, 157, 87])
+    );
+    expect(data.readUInt32LE(8)).to.equal(account.data.length - 12);
+  });
+
+  it("Rejects a range past the end of the account", async () => {
+    const account = await connection.getAccountInfo(metadataPDA, "confirmed");
+    const transaction = await program.methods
+      .getRawTlv(account.data.length - 4, 8)
+      .accounts({ metadata: metadataPDA, mint })
+      .transaction();
+    try {
+      await simulateReturnData(program, transaction, wallet.payer);
+      expect.fail("Read should have failed");
+    } catch (error) {
+      // MetadataError::RangeOutOfBounds
+      expect(error.message).to.contain('"Custom":6015');
+    }
+  });
+});
@@ -0,0 +1,71 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram, updateField } from "./utils";
+
+describe("rebalance rent", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  // Lamports above the rent exempt minimum
+  const surplus = async (metadataPDA: PublicKey) => {
+    const account = await connection.getAccountInfo(metadataPDA, "confirmed");
+    const rent = await connection.getMinimumBalanceForRentExemption(
+      account.data.length
+    );
+    return account.lamports - rent;
+  };
+
+  it("Moves the surplus, keeping both accounts rent exempt", async () => {
+    const source = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "symbol",
+      uri: "uri",
+    });
+    const destination = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "symbol",
+      uri: "uri",
+    });
+
+    // A delegate shrink leaves a surplus held in the source
+    const delegate = Keypair.generate();
+    await program.methods
+      .setDelegate(delegate.publicKey, true)
+      .accounts({
+        metadata: source.metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint: source.mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+    for (const value of ["long value", "short"]) {
+      await updateField(
+        program,
+        wallet.payer,
+        source.mint,
+        "key1",
+        value,
+        delegate
+      );
+    }
+    const held = await surplus(source.metadataPDA);
+    expect(held).to.be.greaterThan(0);
+
+    await program.methods
+      .rebalanceRent()
+      .accounts({
+        sourceMetadata: source.metadataPDA,
+        sourceMint: source.mint,
+        destinationMetadata: destination.metadataPDA,
+        destinationMint: destination.mint,
+        updateAuthority: wallet.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    expect(await surplus(source.metadataPDA)).to.equal(0);
+    expect(await surplus(destination.metadataPDA)).to.equal(held);
+  });
+});
@@ -0,0 +1,140 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  createInitializeMetadataInstruction,
+  createMintInstructions,
+  createPointerMint,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  findRegistryPagePda,
+  findRegistryPda,
+  getProgram,
+} from "./utils";
+
+// Must match REGISTRY_PAGE_SIZE in the program
+const REGISTRY_PAGE_SIZE = 30;
+
+describe("registry", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+  const registryPDA = findRegistryPda(program.programId);
+
+  const registryCount = async () =>
+    (await program.account.registry.fetch(registryPDA)).count.toNumber();
+
+  // Create a mint and initialize its metadata, registering the mint
+  const createRegisteredMint = async (): Promise<PublicKey> => {
+    const mintKeypair = Keypair.generate();
+    const mint = mintKeypair.publicKey;
+    const page = Math.floor((await registryCount()) / REGISTRY_PAGE_SIZE);
+
+    const initializeInstruction = createInitializeMetadataInstruction(
+      program.programId,
+      wallet.publicKey,
+      mint,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    initializeInstruction.keys.push(
+      { isSigner: false, isWritable: true, pubkey: registryPDA },
+      {
+        isSigner: false,
+        isWritable: true,
+        pubkey: findRegistryPagePda(program.programId, page),
+      }
+    );
+
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        ...(await createMintInstructions(
+          connection,
+          wallet.publicKey,
+          mint,
+          findMetadataPda(program.programId, mint)
+        )),
+        initializeInstruction
+      ),
+      [wallet.payer, mintKeypair],
+      { commitment: "confirmed" }
+    );
+    return mint;
+  };
+
+  before(async () => {
+    if (!(await program.account.registry.fetchNullable(registryPDA))) {
+      await program.methods
+        .initializeRegistry()
+        .accounts({
+          registry: registryPDA,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+    }
+  });
+
+  it("Enumerates registered mints", async () => {
+    const countBefore = await registryCount();
+    const mints = [];
+    for (let i = 0; i < 3; i++) {
+      mints.push(await createRegisteredMint());
+    }
+    expect(await registryCount()).to.equal(countBefore + 3);
+
+    // Read every page holding the new mints
+    const registered: string[] = [];
+    const firstPage = Math.floor(countBefore / REGISTRY_PAGE_SIZE);
+    const lastPage = Math.floor((countBefore + 2) / REGISTRY_PAGE_SIZE);
+    for (let page = firstPage; page <= lastPage; page++) {
+      const pageMints = await program.methods
+        .getRegistryPage(page)
+        .accounts({
+          registryPage: findRegistryPagePda(program.programId, page),
+        })
+        .view();
+      registered.push(...pageMints.map((mint) => mint.toBase58()));
+    }
+    expect(registered).to.include.members(mints.map((mint) => mint.toBase58()));
+  });
+
+  it("Registers mints initialized through initializeSelf", async () => {
+    const mint = await createPointerMint(program, wallet.payer);
+    const countBefore = await registryCount();
+    const page = Math.floor(countBefore / REGISTRY_PAGE_SIZE);
+
+    await program.methods
+      .initializeSelf({ name: "name", symbol: "symbol", uri: "uri" })
+      .accounts({
+        metadata: findMetadataPda(program.programId, mint),
+        mint,
+        authority: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+        registry: registryPDA,
+        registryPage: findRegistryPagePda(program.programId, page),
+        symbolClaim: null,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    expect(await registryCount()).to.equal(countBefore + 1);
+    const pageMints = await program.methods
+      .getRegistryPage(page)
+      .accounts({
+        registryPage: findRegistryPagePda(program.programId, page),
+      })
+      .view();
+    expect(pageMints.map((registered) => registered.toBase58())).to.include(
+      mint.toBase58()
+    );
+  });
+});
@@ -0,0 +1,133 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  createMintWithPolicy,
+  fetchMetadata,
+  getKey,
+  getProgram,
+} from "./utils";
+
+describe("relayed update", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+  // Signs off-chain only, the wallet relays and pays
+  const updateAuthority = Keypair.generate();
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  // Relay an update signed by the update authority
+  const relayUpdate = async (
+    key: string,
+    value: string,
+    nonce: number,
+    signer: Keypair = updateAuthority,
+    feeDestination: PublicKey | null = null
+  ) => {
+    const data = { field: { key: [key] }, value };
+    const nonceBytes = Buffer.alloc(8);
+    nonceBytes.writeBigUInt64LE(BigInt(nonce));
+    // Program id, mint, u64 nonce, then the borsh UpdateFieldData
+    const message = Buffer.concat([
+      program.programId.toBuffer(),
+      mint.toBuffer(),
+      nonceBytes,
+      program.coder.types.encode("UpdateFieldData", data),
+    ]);
+
+    const relayInstruction = await program.methods
+      .relayUpdateField(data, new BN(nonce))
+      .accounts({
+        metadata: metadataPDA,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        feeDestination,
+      })
+      .instruction();
+
+    return sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        Ed25519Program.createInstructionWithPrivateKey({
+          privateKey: signer.secretKey,
+          message,
+        }),
+        relayInstruction
+      ),
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+  };
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      updateAuthority.publicKey
+    ));
+  });
+
+  it("Applies an update signed by the update authority", async () => {
+    await relayUpdate("key1", "value1", 1);
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Rejects a replayed nonce", async () => {
+    await relayUpdate("key1", "value2", 2);
+
+    try {
+      await relayUpdate("key1", "value3", 2);
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::NonceAlreadyUsed
+      expect(error.message).to.contain("0x179a");
+    }
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value2");
+  });
+
+  it("Charges the update fee on a relayed update", async () => {
+    const fee = 5000;
+    const feeDestination = Keypair.generate().publicKey;
+    ({ mint, metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { updateFee: { lamports: new BN(fee), destination: feeDestination } }
+    ));
+    const rentExempt = await connection.getMinimumBalanceForRentExemption(0);
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(feeDestination, rentExempt),
+      "confirmed"
+    );
+
+    try {
+      await relayUpdate("key1", "value1", 1, wallet.payer);
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::IncorrectFeeDestination
+      expect(error.message).to.contain("0x17a2");
+    }
+
+    await relayUpdate("key1", "value1", 1, wallet.payer, feeDestination);
+    expect(await connection.getBalance(feeDestination, "confirmed")).to.equal(
+      rentExempt + fee
+    );
+  });
+});
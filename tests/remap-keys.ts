@@ -0,0 +1,46 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("remap keys", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Renames every key with the old prefix", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    await updateField(program, wallet.payer, mint, "old_color", "red");
+    await updateField(program, wallet.payer, mint, "old_size", "large");
+    await updateField(program, wallet.payer, mint, "other", "kept");
+
+    await program.methods
+      .remapKeys("old_", "new_")
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "new_color")).to.equal("red");
+    expect(getKey(metadata, "new_size")).to.equal("large");
+    expect(getKey(metadata, "other")).to.equal("kept");
+    expect(
+      metadata.additionalMetadata.filter(([key]) => key.startsWith("old_"))
+    ).to.be.empty;
+  });
+});
@@ -0,0 +1,58 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("rename key", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: anchor.web3.PublicKey;
+  let metadataPDA: anchor.web3.PublicKey;
+
+  const renameKey = (oldKey: string, newKey: string) =>
+    program.methods
+      .renameKey(oldKey, newKey)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await updateField(program, wallet.payer, mint, "desc", "a description");
+    await updateField(program, wallet.payer, mint, "other", "value");
+  });
+
+  it("Moves the value to the new key", async () => {
+    await renameKey("desc", "description");
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "description")).to.equal("a description");
+    expect(getKey(metadata, "desc")).to.be.undefined;
+  });
+
+  it("Rejects renaming onto an existing key", async () => {
+    try {
+      await renameKey("description", "other");
+      expect.fail("Rename should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("DuplicateKey");
+    }
+  });
+});
@@ -0,0 +1,30 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createMintWithMetadata, getProgram, updateField } from "./utils";
+
+describe("rent info", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  it("Returns the size, rent-exempt minimum and balance", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+
+    const rentInfo = await program.methods
+      .getRentInfo()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+    const account = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(rentInfo.size.toNumber()).to.equal(account.data.length);
+    expect(rentInfo.rentExemptMinimum.toNumber()).to.equal(
+      await connection.getMinimumBalanceForRentExemption(account.data.length)
+    );
+    expect(rentInfo.lamports.toNumber()).to.equal(account.lamports);
+  });
+});
@@ -0,0 +1,65 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("reset", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const resetMetadata = (confirmation: PublicKey) =>
+    program.methods
+      .resetMetadata(confirmation)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+  });
+
+  it("Rejects a wrong confirmation", async () => {
+    try {
+      await resetMetadata(Keypair.generate().publicKey);
+      expect.fail("Reset should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidConfirmation");
+    }
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("name");
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Resets with the mint as confirmation", async () => {
+    await resetMetadata(mint);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.name).to.equal("");
+    expect(metadata.symbol).to.equal("");
+    expect(metadata.uri).to.equal("");
+    expect(getKey(metadata, "key1")).to.be.undefined;
+    // Integrity hash is kept up to date
+    expect(getKey(metadata, "__integrity")).to.not.be.undefined;
+  });
+});
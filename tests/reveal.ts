@@ -0,0 +1,159 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  simulateReturnData,
+  updateField,
+} from "./utils";
+
+describe("reveal", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const accounts = () => ({
+    metadata: metadataPDA,
+    updateAuthority: wallet.publicKey,
+    mint,
+    payer: wallet.publicKey,
+    systemProgram: SystemProgram.programId,
+  });
+
+  const getMetadata = () =>
+    program.methods
+      .getMetadata()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+  const getEntry = (metadata, key: string) =>
+    metadata.additionalMetadata.find((entry) => entry.key === key)?.value;
+
+  const reveal = () =>
+    program.methods
+      .reveal()
+      .accounts(accounts())
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "Rare #1", symbol: "RARE", uri: "https://example.com/1.json" }
+    ));
+    await updateField(program, wallet.payer, mint, "rarity", "legendary");
+
+    for (const [field, placeholder] of [
+      [{ name: {} }, "Mystery"],
+      [{ symbol: {} }, "???"],
+      [{ uri: {} }, "https://example.com/hidden.json"],
+      [{ key: ["rarity"] }, "?"],
+    ]) {
+      await program.methods
+        .hideField(field, placeholder)
+        .accounts(accounts())
+        .rpc({ commitment: "confirmed" });
+    }
+  });
+
+  it("Reads placeholders before the reveal", async () => {
+    const metadata = await getMetadata();
+    expect(metadata.name).to.equal("Mystery");
+    expect(metadata.symbol).to.equal("???");
+    expect(metadata.uri).to.equal("https://example.com/hidden.json");
+    expect(getEntry(metadata, "rarity")).to.equal("?");
+
+    // The real values are stored all along
+    const stored = await fetchMetadata(connection, metadataPDA);
+    expect(stored.name).to.equal("Rare #1");
+    expect(stored.uri).to.equal("https://example.com/1.json");
+  });
+
+  it("Masks every other read before the reveal", async () => {
+    const read = { metadata: metadataPDA, mint };
+    const uris = await program.methods.getUris().accounts(read).view();
+    expect(uris.uri).to.equal("https://example.com/hidden.json");
+    const metaplex = await program.methods
+      .exportMetaplex()
+      .accounts(read)
+      .view();
+    expect(metaplex.name).to.equal("Mystery");
+
+    // No locale variant, the fallback is the placeholder
+    const localized = await program.methods
+      .getLocalized("fr")
+      .accounts(read)
+      .view();
+    expect(localized).to.deep.equal({ name: "Mystery", symbol: "???" });
+
+    const filter = (symbol: string) =>
+      program.methods
+        .filterBySymbol(symbol)
+        .accounts({ tokenMetadataProgram: program.programId })
+        .remainingAccounts([
+          { pubkey: metadataPDA, isSigner: false, isWritable: false },
+        ])
+        .view();
+    expect(await filter("RARE")).to.be.empty;
+    expect((await filter("???")).map((key) => key.toBase58())).to.deep.equal([
+      mint.toBase58(),
+    ]);
+
+    // Same stored values, nothing hidden
+    const twin = await createMintWithMetadata(program, wallet.payer, {
+      name: "Rare #1",
+      symbol: "RARE",
+      uri: "https://example.com/1.json",
+    });
+    const diff = await program.methods
+      .diffMetadata()
+      .accounts({ metadataA: metadataPDA, metadataB: twin.metadataPDA })
+      .view();
+    expect(diff.name).to.be.true;
+    expect(diff.symbol).to.be.true;
+    expect(diff.uri).to.be.true;
+  });
+
+  it("Leaves the raw TLV unmasked", async () => {
+    const read = { metadata: metadataPDA, mint };
+
+    // Header first for the length
+    const rawTlv = async (start: number, len: number) =>
+      simulateReturnData(
+        program,
+        await program.methods
+          .getRawTlv(start, len)
+          .accounts(read)
+          .transaction(),
+        wallet.payer
+      );
+    const header = await rawTlv(0, 12);
+    const data = await rawTlv(0, 12 + header.readUInt32LE(8));
+    // The stored value, next to the placeholder kept under its hidden key
+    expect(data.includes(Buffer.from("Rare #1"))).to.be.true;
+    expect(data.includes(Buffer.from("Mystery"))).to.be.true;
+  });
+
+  it("Reads the real values after the reveal", async () => {
+    await reveal();
+
+    const metadata = await getMetadata();
+    expect(metadata.name).to.equal("Rare #1");
+    expect(metadata.uri).to.equal("https://example.com/1.json");
+    expect(getEntry(metadata, "rarity")).to.equal("legendary");
+  });
+
+  it("Rejects a second reveal", async () => {
+    try {
+      await reveal();
+      expect.fail("Reveal should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("AlreadyRevealed");
+    }
+  });
+});
@@ -0,0 +1,55 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+} from "./utils";
+
+describe("set key if absent", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: PublicKey;
+  let metadataPDA: PublicKey;
+
+  const setKeyIfAbsent = (key: string, value: string) =>
+    program.methods
+      .setKeyIfAbsent(key, value)
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Writes a new key", async () => {
+    await setKeyIfAbsent("origin", "first");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "origin")).to.equal("first");
+  });
+
+  it("Rejects overwriting an existing key", async () => {
+    try {
+      await setKeyIfAbsent("origin", "second");
+      expect.fail("Set should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("KeyAlreadyExists");
+    }
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "origin")).to.equal("first");
+  });
+});
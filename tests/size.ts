@@ -0,0 +1,110 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { pack } from "@solana/spl-token-metadata";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("metadata size", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  for (const length of [0, 1, 32, 200]) {
+    it(`Packs metadata exactly for a ${length} byte name`, async () => {
+      const { metadataPDA } = await createMintWithMetadata(
+        program,
+        wallet.payer,
+        { name: "n".repeat(length), symbol: "symbol", uri: "uri" }
+      );
+
+      // 8 byte discriminator, 4 byte length, then the packed value
+      const { data } = await connection.getAccountInfo(
+        metadataPDA,
+        "confirmed"
+      );
+      expect(data.length).to.equal(12 + data.readUInt32LE(8));
+    });
+  }
+
+  it("Stores an empty symbol in minimal space", async () => {
+    const { metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "", uri: "uri" }
+    );
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(metadata.symbol).to.equal("");
+
+    // Only the 4 byte length prefix is stored for the empty symbol
+    const { data } = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(data.length).to.equal(12 + pack(metadata).length);
+  });
+
+  it("Rejects metadata with all core fields empty", async () => {
+    try {
+      await createMintWithMetadata(program, wallet.payer, {
+        name: "",
+        symbol: "",
+        uri: "",
+      });
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      // MetadataError::EmptyMetadata
+      expect(error.message).to.contain("0x1784");
+    }
+  });
+
+  it("Rejects growing the account past the maximum size", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    // Each 500 byte value adds a little over 500 bytes, must match MAX_METADATA_SIZE
+    const maxSize = 10240;
+    const value = "v".repeat(500);
+    let size = (await connection.getAccountInfo(metadataPDA, "confirmed")).data
+      .length;
+    let i = 0;
+    try {
+      for (; size <= maxSize; i++) {
+        await updateField(program, wallet.payer, mint, `key${i}`, value);
+        size = (await connection.getAccountInfo(metadataPDA, "confirmed")).data
+          .length;
+      }
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::MetadataTooLarge
+      expect(error.message).to.contain("0x1795");
+    }
+    expect(size).to.be.at.most(maxSize);
+    expect(i).to.be.greaterThan(0);
+  });
+
+  it("Reads the account and packed value lengths", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+
+    const size = await program.methods
+      .getSize()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+
+    const account = await connection.getAccountInfo(metadataPDA, "confirmed");
+    expect(size.dataLen.toNumber()).to.equal(account.data.length);
+    expect(size.valueLen).to.equal(account.data.readUInt32LE(8));
+    expect(size.valueLen).to.equal(
+      pack(await fetchMetadata(connection, metadataPDA)).length
+    );
+  });
+});
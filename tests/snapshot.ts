@@ -0,0 +1,61 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createHash } from "crypto";
+import { TokenMetadata, pack } from "@solana/spl-token-metadata";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getProgram,
+  updateField,
+} from "./utils";
+
+// Hash the metadata without its integrity key, matching the program
+function snapshotHash(metadata: TokenMetadata): number[] {
+  const canonical = {
+    ...metadata,
+    additionalMetadata: metadata.additionalMetadata.filter(
+      ([key]) => key !== "__integrity"
+    ),
+  };
+  return [...createHash("sha256").update(pack(canonical)).digest()];
+}
+
+describe("snapshot", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: anchor.web3.PublicKey;
+  let metadataPDA: anchor.web3.PublicKey;
+  let snapshot: number[];
+
+  const verifySnapshot = (expectedHash: number[]) =>
+    program.methods
+      .verifySnapshot(expectedHash)
+      .accounts({ metadata: metadataPDA, mint })
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    snapshot = snapshotHash(await fetchMetadata(connection, metadataPDA));
+  });
+
+  it("Verifies an unchanged snapshot", async () => {
+    await verifySnapshot(snapshot);
+  });
+
+  it("Rejects a snapshot after a field changed", async () => {
+    await updateField(program, wallet.payer, mint, "key1", "value1");
+
+    try {
+      await verifySnapshot(snapshot);
+      expect.fail("Verify should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("SnapshotMismatch");
+    }
+  });
+});
@@ -0,0 +1,65 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("swap keys", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: anchor.web3.PublicKey;
+  let metadataPDA: anchor.web3.PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+    await updateField(program, wallet.payer, mint, "first", "a");
+    await updateField(program, wallet.payer, mint, "second", "a longer value");
+  });
+
+  it("Swaps the values of two keys", async () => {
+    await program.methods
+      .swapKeys("first", "second")
+      .accounts({
+        metadata: metadataPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "first")).to.equal("a longer value");
+    expect(getKey(metadata, "second")).to.equal("a");
+  });
+
+  it("Rejects swapping a missing key", async () => {
+    try {
+      await program.methods
+        .swapKeys("first", "missing")
+        .accounts({
+          metadata: metadataPDA,
+          updateAuthority: wallet.publicKey,
+          mint,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Swap should have failed");
+    } catch (error) {
+      // TokenMetadataError::KeyNotFound
+      expect(error.message).to.contain("0x35c2b5c2");
+    }
+  });
+});
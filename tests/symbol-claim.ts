@@ -0,0 +1,117 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Field } from "@solana/spl-token-metadata";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createPointerMint,
+  findApprovalPda,
+  findConfigPda,
+  findMetadataPda,
+  findSymbolClaimPda,
+  getProgram,
+  updateField,
+} from "./utils";
+
+describe("symbol claim", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const symbol = "UNIQ";
+  const symbolClaimPDA = findSymbolClaimPda(program.programId, symbol);
+
+  // Create a mint and initialize its metadata, claiming the symbol
+  const initializeClaiming = async (claimed = symbol): Promise<PublicKey> => {
+    const mint = await createPointerMint(program, wallet.payer);
+    await program.methods
+      .initialize({ name: "name", symbol: claimed, uri: "uri" })
+      .accounts({
+        metadata: findMetadataPda(program.programId, mint),
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+        symbolClaim: findSymbolClaimPda(program.programId, claimed),
+      })
+      .rpc({ commitment: "confirmed" });
+    return mint;
+  };
+
+  let firstMint: PublicKey;
+
+  it("Claims the symbol at initialize", async () => {
+    firstMint = await initializeClaiming();
+    const claim = await program.account.symbolClaim.fetch(
+      symbolClaimPDA,
+      "confirmed"
+    );
+    expect(claim.mint.toBase58()).to.equal(firstMint.toBase58());
+  });
+
+  it("Rejects a duplicate claim", async () => {
+    try {
+      await initializeClaiming();
+      expect.fail("Initialize should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("SymbolAlreadyTaken");
+    }
+  });
+
+  it("Reclaims the symbol after the metadata is closed", async () => {
+    await program.methods
+      .closeMetadata()
+      .accounts({
+        metadata: findMetadataPda(program.programId, firstMint),
+        updateAuthority: wallet.publicKey,
+        mint: firstMint,
+        symbolClaim: symbolClaimPDA,
+        destination: wallet.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const secondMint = await initializeClaiming();
+    const claim = await program.account.symbolClaim.fetch(
+      symbolClaimPDA,
+      "confirmed"
+    );
+    expect(claim.mint.toBase58()).to.equal(secondMint.toBase58());
+  });
+
+  it("Keeps a claimed symbol until the metadata is closed", async () => {
+    const claimedSymbol = "KEEP";
+    const claimPDA = findSymbolClaimPda(program.programId, claimedSymbol);
+    const mint = await initializeClaiming(claimedSymbol);
+
+    try {
+      await updateField(program, wallet.payer, mint, Field.Symbol, "OTHER");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::SymbolClaimed
+      expect(error.message).to.contain("0x17ab");
+    }
+
+    await program.methods
+      .closeMetadata()
+      .accounts({
+        metadata: findMetadataPda(program.programId, mint),
+        updateAuthority: wallet.publicKey,
+        mint,
+        symbolClaim: claimPDA,
+        destination: wallet.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+    const freed = await program.provider.connection.getAccountInfo(
+      claimPDA,
+      "confirmed"
+    );
+    expect(freed).to.be.null;
+
+    const secondMint = await initializeClaiming(claimedSymbol);
+    const claim = await program.account.symbolClaim.fetch(
+      claimPDA,
+      "confirmed"
+    );
+    expect(claim.mint.toBase58()).to.equal(secondMint.toBase58());
+  });
+});
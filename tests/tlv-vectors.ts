@@ -0,0 +1,146 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, Transaction, sendAndConfirmTransaction } from "@solana/web3.js";
+import {
+  createInitializeMetadataInstruction,
+  createMintInstructions,
+  findMetadataPda,
+  getProgram,
+  updateField,
+} from "./utils";
+
+// Fixed packed bytes of metadata accounts, so a dependency bump that changes the
+// TLV encoding fails here instead of leaving existing accounts unreadable.
+// Keypairs come from fixed seeds to make the bytes, including the integrity hash,
+// deterministic. The mints can only be created once per validator.
+const seededKeypair = (seed: number) =>
+  Keypair.fromSeed(new Uint8Array(32).fill(seed));
+
+const vectors = [
+  {
+    description: "empty symbol and uri",
+    mintSeed: 2,
+    metadata: { name: "N", symbol: "", uri: "" },
+    additionalMetadata: [],
+    expected: [
+      "70845a5a0b589d57", // TokenMetadata discriminator
+      "90000000", // length 144
+      "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c", // update authority
+      "8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394", // mint
+      "010000004e", // name "N"
+      "00000000", // symbol ""
+      "00000000", // uri ""
+      "01000000", // 1 additional metadata entry
+      "0b0000005f5f696e74656772697479", // key "__integrity"
+      "2c00000033664b38626d696d786662366231765454464370695331646437426b7a324855455968446b4155314c507947", // value "3fK8bmimxfb6b1vTTFCpiS1dd7Bkz2HUEYhDkAU1LPyG"
+    ],
+  },
+  {
+    description: "small metadata",
+    mintSeed: 3,
+    metadata: {
+      name: "Token",
+      symbol: "TKN",
+      uri: "https://example.com/token.json",
+    },
+    additionalMetadata: [],
+    expected: [
+      "70845a5a0b589d57", // TokenMetadata discriminator
+      "b5000000", // length 181
+      "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c", // update authority
+      "ed4928c628d1c2c6eae90338905995612959273a5c63f93636c14614ac8737d1", // mint
+      "05000000546f6b656e", // name "Token"
+      "03000000544b4e", // symbol "TKN"
+      "1e00000068747470733a2f2f6578616d706c652e636f6d2f746f6b656e2e6a736f6e", // uri "https://example.com/token.json"
+      "01000000", // 1 additional metadata entry
+      "0b0000005f5f696e74656772697479", // key "__integrity"
+      "2c00000036376471457a564677723543503446426b3636596434416b453261534e5369694a7a774c3256375167557076", // value "67dqEzVFwr5CP4FBk66Yd4AkE2aSNSiiJzwL2V7QgUpv"
+    ],
+  },
+  {
+    description: "multiple keys",
+    mintSeed: 4,
+    metadata: {
+      name: "Token",
+      symbol: "TKN",
+      uri: "https://example.com/token.json",
+    },
+    additionalMetadata: [
+      ["key1", "value1"],
+      ["key2", "value2"],
+    ],
+    expected: [
+      "70845a5a0b589d57", // TokenMetadata discriminator
+      "1b010000", // length 283
+      "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c", // update authority
+      "ca93ac1705187071d67b83c7ff0efe8108e8ec4530575d7726879333dbdabe7c", // mint
+      "05000000546f6b656e", // name "Token"
+      "03000000544b4e", // symbol "TKN"
+      "1e00000068747470733a2f2f6578616d706c652e636f6d2f746f6b656e2e6a736f6e", // uri "https://example.com/token.json"
+      "04000000", // 4 additional metadata entries
+      "0b0000005f5f696e74656772697479", // key "__integrity"
+      "2c00000037345932396d706237583852634442316d37744d6471366f5332435047647350454e376b58773772746a344a", // value "74Y29mpb7X8RcDB1m7tMdq6oS2CPGdsPEN7kXw7rtj4J"
+      "0e0000005f5f6c6173745f75706461746572", // key "__last_updater"
+      "2c000000414b6e4c344e4e66334447575a4a533663506b6e427545476e5673563441346d35746765624c486152535a39", // value "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9"
+      "040000006b657931", // key "key1"
+      "0600000076616c756531", // value "value1"
+      "040000006b657932", // key "key2"
+      "0600000076616c756532", // value "value2"
+    ],
+  },
+];
+
+describe("tlv vectors", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+  const updateAuthority = seededKeypair(1);
+
+  for (const vector of vectors) {
+    it(`Packs ${vector.description} exactly`, async () => {
+      const mintKeypair = seededKeypair(vector.mintSeed);
+      const mint = mintKeypair.publicKey;
+      const metadataPDA = findMetadataPda(program.programId, mint);
+
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          ...(await createMintInstructions(
+            connection,
+            wallet.publicKey,
+            mint,
+            metadataPDA
+          )),
+          createInitializeMetadataInstruction(
+            program.programId,
+            wallet.publicKey,
+            mint,
+            vector.metadata,
+            updateAuthority.publicKey
+          )
+        ),
+        [wallet.payer, mintKeypair],
+        { commitment: "confirmed" }
+      );
+      for (const [key, value] of vector.additionalMetadata) {
+        await updateField(
+          program,
+          wallet.payer,
+          mint,
+          key,
+          value,
+          updateAuthority
+        );
+      }
+
+      const { data } = await connection.getAccountInfo(
+        metadataPDA,
+        "confirmed"
+      );
+      const expected = Buffer.from(vector.expected.join(""), "hex");
+      expect(data.toString("hex")).to.equal(expected.toString("hex"));
+      // Account is sized exactly to the TLV entry
+      expect(data.length).to.equal(expected.length);
+    });
+  }
+});
@@ -46,6 +46,16 @@ describe("token-metadata", () => {
     program.programId
   );
 
+  // PDAs used to check the mint is approved, if required by the program config
+  const [configPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [approvalPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("approved"), mintPublicKey.toBuffer()],
+    program.programId
+  );
+
   // Define token metadata
   const metaData: TokenMetadata = {
     updateAuthority: wallet.publicKey,
@@ -109,7 +119,9 @@ describe("token-metadata", () => {
     // Used to create the metadata account via CPI in the program instruction
     initializeMetadataInstruction.keys.push(
       { isSigner: true, isWritable: true, pubkey: wallet.publicKey },
-      { isSigner: false, isWritable: false, pubkey: SystemProgram.programId }
+      { isSigner: false, isWritable: false, pubkey: SystemProgram.programId },
+      { isSigner: false, isWritable: false, pubkey: configPDA },
+      { isSigner: false, isWritable: false, pubkey: approvalPDA }
     );
 
     const transaction = new Transaction().add(
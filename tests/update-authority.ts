@@ -0,0 +1,73 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PublicKey } from "@solana/web3.js";
+import { createMintWithMetadata, getProgram } from "./utils";
+
+describe("get update authority", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  it("Returns a mutable update authority", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    const updateAuthority = await program.methods
+      .getUpdateAuthority()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(updateAuthority.toBase58()).to.equal(wallet.publicKey.toBase58());
+  });
+
+  it("Returns the default pubkey for immutable metadata", async () => {
+    // Passing the program id as the optional update authority leaves it unset
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      program.programId
+    );
+
+    const updateAuthority = await program.methods
+      .getUpdateAuthority()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(updateAuthority.toBase58()).to.equal(PublicKey.default.toBase58());
+  });
+});
+
+describe("get raw authority", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+
+  it("Returns the bytes of a set authority", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    const raw = await program.methods
+      .getRawAuthority()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(Buffer.from(raw)).to.deep.equal(wallet.publicKey.toBuffer());
+  });
+
+  it("Returns all zeros for immutable metadata", async () => {
+    const { mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      program.programId
+    );
+
+    const raw = await program.methods
+      .getRawAuthority()
+      .accounts({ metadata: metadataPDA, mint })
+      .view();
+    expect(Buffer.from(raw)).to.deep.equal(Buffer.alloc(32));
+  });
+});
@@ -0,0 +1,89 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { createMintWithPolicy, findMetadataPda, getProgram } from "./utils";
+
+describe("update fee", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const fee = 5000;
+  const feeDestination = Keypair.generate().publicKey;
+
+  const updateField = (
+    mint: PublicKey,
+    value: string,
+    destination: PublicKey | null = feeDestination
+  ) =>
+    program.methods
+      .updateField({ field: { key: ["key1"] }, value })
+      .accounts({
+        metadata: findMetadataPda(program.programId, mint),
+        updateAuthority: wallet.publicKey,
+        mint,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        instructions: null,
+        changeLog: null,
+        feeDestination: destination,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const destinationBalance = () =>
+    connection.getBalance(feeDestination, "confirmed");
+
+  it("Credits the fee destination on each update", async () => {
+    const { mint } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "TKN", uri: "uri" },
+      { updateFee: { lamports: new BN(fee), destination: feeDestination } }
+    );
+
+    // The first fee has to bring the new account to the rent-exempt minimum
+    const rentExempt = await connection.getMinimumBalanceForRentExemption(0);
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(feeDestination, rentExempt),
+      "confirmed"
+    );
+
+    const before = await destinationBalance();
+    await updateField(mint, "value1");
+    expect(await destinationBalance()).to.equal(before + fee);
+    await updateField(mint, "value2");
+    expect(await destinationBalance()).to.equal(before + 2 * fee);
+  });
+
+  it("Rejects an update without the fee destination", async () => {
+    const { mint } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "TKN", uri: "uri" },
+      { updateFee: { lamports: new BN(fee), destination: feeDestination } }
+    );
+
+    for (const destination of [null, Keypair.generate().publicKey]) {
+      try {
+        await updateField(mint, "value1", destination);
+        expect.fail("Update should have failed");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("IncorrectFeeDestination");
+      }
+    }
+  });
+
+  it("Charges nothing with a zero fee", async () => {
+    const { mint } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "TKN", uri: "uri" },
+      { updateFee: { lamports: new BN(0), destination: feeDestination } }
+    );
+
+    const before = await destinationBalance();
+    await updateField(mint, "value1", null);
+    expect(await destinationBalance()).to.equal(before);
+  });
+});
@@ -0,0 +1,89 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  createMintWithMetadata,
+  createMintWithPolicy,
+  fetchMetadata,
+  findApprovalPda,
+  findConfigPda,
+  findLabeledMetadataPda,
+  getKey,
+  getProgram,
+} from "./utils";
+
+describe("update field without mint", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  const updateFieldNoMint = (metadata: PublicKey, value: string) =>
+    program.methods
+      .updateFieldNoMint({ field: { key: ["key1"] }, value })
+      .accounts({
+        metadata,
+        updateAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  it("Updates a field without the mint account", async () => {
+    const { metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    );
+
+    await updateFieldNoMint(metadataPDA, "value1");
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key1")).to.equal("value1");
+  });
+
+  it("Rejects metadata not at the PDA of its stored mint", async () => {
+    // Labeled metadata stores the same mint at a different address
+    const { mint } = await createMintWithMetadata(program, wallet.payer, {
+      name: "name",
+      symbol: "symbol",
+      uri: "uri",
+    });
+    const labeledPDA = findLabeledMetadataPda(program.programId, mint, "en");
+    await program.methods
+      .initializeLabeled("en", { name: "name", symbol: "TKN", uri: "uri" })
+      .accounts({
+        metadata: labeledPDA,
+        updateAuthority: wallet.publicKey,
+        mint,
+        mintAuthority: wallet.publicKey,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        config: findConfigPda(program.programId),
+        approval: findApprovalPda(program.programId, mint),
+      })
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await updateFieldNoMint(labeledPDA, "value1");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("ConstraintSeeds");
+    }
+  });
+
+  it("Rejects an update under a policy reading the mint", async () => {
+    const { metadataPDA } = await createMintWithPolicy(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" },
+      { supplyThreshold: new BN(0) }
+    );
+
+    try {
+      await updateFieldNoMint(metadataPDA, "value1");
+      expect.fail("Update should have failed");
+    } catch (error) {
+      expect(error.message).to.contain("insufficient account keys");
+    }
+  });
+});
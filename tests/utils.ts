@@ -0,0 +1,378 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { createHash } from "crypto";
+import { TokenMetadata as AnchorTokenMetadata } from "../target/types/token_metadata";
+import {
+  AccountMeta,
+  Connection,
+  Keypair,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+  sendAndConfirmTransaction,
+  PublicKey,
+} from "@solana/web3.js";
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  createInitializeMintInstruction,
+  getMintLen,
+  createInitializeMetadataPointerInstruction,
+} from "@solana/spl-token";
+import {
+  createInitializeInstruction,
+  createUpdateFieldInstruction,
+  unpack,
+  Field,
+  TokenMetadata,
+} from "@solana/spl-token-metadata";
+
+// Find the Program Derived Address (PDA) for metadata
+export function findMetadataPda(
+  programId: PublicKey,
+  mint: PublicKey
+): PublicKey {
+  const [metadataPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("metadata"), mint.toBuffer()],
+    programId
+  );
+  return metadataPDA;
+}
+
+// Find the PDA for the program config
+export function findConfigPda(programId: PublicKey): PublicKey {
+  const [configPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    programId
+  );
+  return configPDA;
+}
+
+// Find the PDA approving a mint to have metadata
+export function findApprovalPda(programId: PublicKey, mint: PublicKey) {
+  const [approvalPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("approved"), mint.toBuffer()],
+    programId
+  );
+  return approvalPDA;
+}
+
+// Find the PDA counting registered mints
+export function findRegistryPda(programId: PublicKey): PublicKey {
+  const [registryPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("registry")],
+    programId
+  );
+  return registryPDA;
+}
+
+// Find the PDA for a page of registered mints
+export function findRegistryPagePda(
+  programId: PublicKey,
+  page: number
+): PublicKey {
+  const pageSeed = Buffer.alloc(4);
+  pageSeed.writeUInt32LE(page);
+  const [registryPagePDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("registry"), pageSeed],
+    programId
+  );
+  return registryPagePDA;
+}
+
+// Find the PDA recording the updates to a mint's metadata
+export function findChangeLogPda(
+  programId: PublicKey,
+  mint: PublicKey
+): PublicKey {
+  const [changeLogPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("change_log"), mint.toBuffer()],
+    programId
+  );
+  return changeLogPDA;
+}
+
+// Find the PDA claiming a symbol for one mint
+export function findSymbolClaimPda(
+  programId: PublicKey,
+  symbol: string
+): PublicKey {
+  const [symbolClaimPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("symbol"), createHash("sha256").update(symbol).digest()],
+    programId
+  );
+  return symbolClaimPDA;
+}
+
+// Find the PDA for metadata stored under a label
+export function findLabeledMetadataPda(
+  programId: PublicKey,
+  mint: PublicKey,
+  label: string
+): PublicKey {
+  const [metadataPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("metadata"), mint.toBuffer(), Buffer.from(label)],
+    programId
+  );
+  return metadataPDA;
+}
+
+// Instructions creating a Token-2022 mint with a metadata pointer to the PDA
+export async function createMintInstructions(
+  connection: Connection,
+  payer: PublicKey,
+  mint: PublicKey,
+  metadataPDA: PublicKey,
+  decimals = 9,
+  freezeAuthority: PublicKey | null = null
+): Promise<TransactionInstruction[]> {
+  const mintLen = getMintLen([ExtensionType.MetadataPointer]);
+  const lamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+
+  return [
+    SystemProgram.createAccount({
+      fromPubkey: payer,
+      newAccountPubkey: mint,
+      space: mintLen,
+      lamports,
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    createInitializeMetadataPointerInstruction(
+      mint,
+      payer,
+      metadataPDA,
+      TOKEN_2022_PROGRAM_ID
+    ),
+    createInitializeMintInstruction(
+      mint,
+      decimals,
+      payer, // Mint authority
+      freezeAuthority,
+      TOKEN_2022_PROGRAM_ID
+    ),
+  ];
+}
+
+// Initialize instruction through the token metadata interface
+export function createInitializeMetadataInstruction(
+  programId: PublicKey,
+  payer: PublicKey,
+  mint: PublicKey,
+  metadata: { name: string; symbol: string; uri: string },
+  updateAuthority: PublicKey = payer,
+  mintAuthority: PublicKey = payer
+): TransactionInstruction {
+  const initializeMetadataInstruction = createInitializeInstruction({
+    programId,
+    metadata: findMetadataPda(programId, mint),
+    updateAuthority,
+    mint,
+    mintAuthority,
+    name: metadata.name,
+    symbol: metadata.symbol,
+    uri: metadata.uri,
+  });
+  // Additional accounts required by our instruction
+  initializeMetadataInstruction.keys.push(
+    { isSigner: true, isWritable: true, pubkey: payer },
+    { isSigner: false, isWritable: false, pubkey: SystemProgram.programId },
+    { isSigner: false, isWritable: false, pubkey: findConfigPda(programId) },
+    {
+      isSigner: false,
+      isWritable: false,
+      pubkey: findApprovalPda(programId, mint),
+    }
+  );
+  return initializeMetadataInstruction;
+}
+
+// Create a Token-2022 mint with a metadata pointer to our metadata PDA
+export async function createPointerMint(
+  program: Program<AnchorTokenMetadata>,
+  payer: Keypair,
+  decimals = 9,
+  freezeAuthority: PublicKey | null = null
+): Promise<PublicKey> {
+  const connection = program.provider.connection;
+  const mintKeypair = Keypair.generate();
+  const mint = mintKeypair.publicKey;
+
+  await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(
+      ...(await createMintInstructions(
+        connection,
+        payer.publicKey,
+        mint,
+        findMetadataPda(program.programId, mint),
+        decimals,
+        freezeAuthority
+      ))
+    ),
+    [payer, mintKeypair],
+    { commitment: "confirmed" }
+  );
+  return mint;
+}
+
+// Create a Token-2022 mint with a metadata pointer to our metadata PDA
+// and initialize the metadata account in the same transaction
+export async function createMintWithMetadata(
+  program: Program<AnchorTokenMetadata>,
+  payer: Keypair,
+  metadata: { name: string; symbol: string; uri: string },
+  updateAuthority: PublicKey = payer.publicKey
+): Promise<{ mint: PublicKey; metadataPDA: PublicKey }> {
+  const connection = program.provider.connection;
+  const mintKeypair = Keypair.generate();
+  const mint = mintKeypair.publicKey;
+  const metadataPDA = findMetadataPda(program.programId, mint);
+
+  const transaction = new Transaction().add(
+    ...(await createMintInstructions(
+      connection,
+      payer.publicKey,
+      mint,
+      metadataPDA
+    )),
+    createInitializeMetadataInstruction(
+      program.programId,
+      payer.publicKey,
+      mint,
+      metadata,
+      updateAuthority
+    )
+  );
+
+  await sendAndConfirmTransaction(
+    connection,
+    transaction,
+    [payer, mintKeypair],
+    { commitment: "confirmed" }
+  );
+  return { mint, metadataPDA };
+}
+
+// Create a mint and initialize its metadata with the given policy
+export async function createMintWithPolicy(
+  program: Program<AnchorTokenMetadata>,
+  payer: Keypair,
+  metadata: { name: string; symbol: string; uri: string },
+  policy: object
+): Promise<{ mint: PublicKey; metadataPDA: PublicKey }> {
+  const mint = await createPointerMint(program, payer);
+  const metadataPDA = findMetadataPda(program.programId, mint);
+  await program.methods
+    .initializeWithPolicy(metadata, policy)
+    .accounts({
+      metadata: metadataPDA,
+      updateAuthority: payer.publicKey,
+      mint,
+      mintAuthority: payer.publicKey,
+      payer: payer.publicKey,
+      systemProgram: SystemProgram.programId,
+      config: findConfigPda(program.programId),
+      approval: findApprovalPda(program.programId, mint),
+    })
+    .signers([payer])
+    .rpc({ commitment: "confirmed" });
+  return { mint, metadataPDA };
+}
+
+// Send an UpdateField instruction through the token metadata interface
+export async function updateField(
+  program: Program<AnchorTokenMetadata>,
+  payer: Keypair,
+  mint: PublicKey,
+  field: Field | string,
+  value: string,
+  updateAuthority: Keypair = payer
+): Promise<string> {
+  const connection = program.provider.connection;
+  const updateFieldInstruction = createUpdateFieldInstruction({
+    programId: program.programId,
+    metadata: findMetadataPda(program.programId, mint),
+    updateAuthority: updateAuthority.publicKey,
+    field,
+    value,
+  });
+  // Additional accounts required by our instruction
+  updateFieldInstruction.keys.push(
+    { isSigner: false, isWritable: false, pubkey: mint },
+    { isSigner: true, isWritable: true, pubkey: payer.publicKey },
+    { isSigner: false, isWritable: false, pubkey: SystemProgram.programId }
+  );
+
+  const signers =
+    updateAuthority === payer ? [payer] : [payer, updateAuthority];
+  return sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(updateFieldInstruction),
+    signers,
+    { commitment: "confirmed" }
+  );
+}
+
+// Fetch and unpack the TokenMetadata stored in the metadata account
+export async function fetchMetadata(
+  connection: Connection,
+  metadataPDA: PublicKey
+): Promise<TokenMetadata> {
+  const metadataAccount = await connection.getAccountInfo(
+    metadataPDA,
+    "confirmed"
+  );
+  // Metadata starts after the 8 byte discriminator and 4 byte length
+  return unpack(metadataAccount.data.subarray(12));
+}
+
+// Look up a custom key in the additional metadata
+export function getKey(
+  metadata: TokenMetadata,
+  key: string
+): string | undefined {
+  const entry = metadata.additionalMetadata.find(([k]) => k === key);
+  return entry ? entry[1] : undefined;
+}
+
+// Simulate a transaction and return the raw return data set by our program
+export async function simulateReturnData(
+  program: Program<AnchorTokenMetadata>,
+  transaction: Transaction,
+  payer: Keypair
+): Promise<Buffer> {
+  const { value } = await program.provider.connection.simulateTransaction(
+    transaction,
+    [payer]
+  );
+  if (value.err) {
+    throw new Error(`Simulation failed: ${JSON.stringify(value.err)}`);
+  }
+  return Buffer.from(value.returnData.data[0], "base64");
+}
+
+// Instruction of a test-only helper, dispatched by the fallback of
+// `test-helpers` builds and left out of the IDL, built from its Anchor
+// discriminator and borsh arguments
+export function createTestHelperInstruction(
+  programId: PublicKey,
+  name: string,
+  keys: AccountMeta[],
+  args: Buffer = Buffer.alloc(0)
+): TransactionInstruction {
+  const discriminator = createHash("sha256")
+    .update(`global:${name}`)
+    .digest()
+    .subarray(0, 8);
+  return new TransactionInstruction({
+    programId,
+    keys,
+    data: Buffer.concat([discriminator, args]),
+  });
+}
+
+export function getProgram(): Program<AnchorTokenMetadata> {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  return anchor.workspace.TokenMetadata as Program<AnchorTokenMetadata>;
+}
@@ -0,0 +1,53 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  createMintWithMetadata,
+  fetchMetadata,
+  getKey,
+  getProgram,
+  updateField,
+} from "./utils";
+
+// Must match MAX_VALUE_LEN in the program
+const MAX_VALUE_LEN = 512;
+
+describe("value length", () => {
+  const program = getProgram();
+  const wallet = program.provider.wallet as anchor.Wallet;
+  const connection = program.provider.connection;
+
+  let mint: anchor.web3.PublicKey;
+  let metadataPDA: anchor.web3.PublicKey;
+
+  before(async () => {
+    ({ mint, metadataPDA } = await createMintWithMetadata(
+      program,
+      wallet.payer,
+      { name: "name", symbol: "symbol", uri: "uri" }
+    ));
+  });
+
+  it("Accepts a value at the limit", async () => {
+    const value = "v".repeat(MAX_VALUE_LEN);
+    await updateField(program, wallet.payer, mint, "key", value);
+
+    const metadata = await fetchMetadata(connection, metadataPDA);
+    expect(getKey(metadata, "key")).to.equal(value);
+  });
+
+  it("Rejects a value over the limit", async () => {
+    try {
+      await updateField(
+        program,
+        wallet.payer,
+        mint,
+        "key",
+        "v".repeat(MAX_VALUE_LEN + 1)
+      );
+      expect.fail("Update should have failed");
+    } catch (error) {
+      // MetadataError::ValueTooLong
+      expect(error.message).to.contain("0x1785");
+    }
+  });
+});